@@ -1,4 +1,9 @@
 use std::ops::Range;
+use std::time::Duration;
+
+use criterion::measurement::WallTime;
+use criterion::{BenchmarkGroup, Criterion};
+use rope_benches::rope::{Operation, Rope};
 
 pub const TINY: &str = include_str!("tiny.txt");
 pub const SMALL: &str = include_str!("small.txt");
@@ -42,3 +47,28 @@ impl Iterator for PercentRanges {
         Some(range)
     }
 }
+
+/// Runs `bench` in the `{rope}_{op}` benchmark group if `R` supports `op`.
+/// Otherwise the group only gets a `not_supported` benchmark which does
+/// nothing, since criterion can't record skipped benchmarks, so that the pair
+/// still shows up in the report.
+#[allow(dead_code)]
+pub fn bench_group<R: Rope>(
+    c: &mut Criterion,
+    op: Operation,
+    bench: impl FnOnce(&mut BenchmarkGroup<WallTime>),
+) {
+    let name = format!("{}_{op}", R::name().to_lowercase());
+
+    let mut group = c.benchmark_group(name);
+
+    if R::supports(op) {
+        bench(&mut group);
+    } else {
+        group
+            .sample_size(10)
+            .warm_up_time(Duration::from_millis(1))
+            .measurement_time(Duration::from_millis(1))
+            .bench_function("not_supported", |b| b.iter(|| ()));
+    }
+}
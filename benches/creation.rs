@@ -1,110 +1,12 @@
 mod common;
 
-use common::{LARGE, MEDIUM, SMALL, TINY};
+use common::{bench_group, LARGE, MEDIUM, SMALL, TINY};
 use criterion::measurement::WallTime;
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkGroup, Criterion};
-
-trait Rope {
-    fn from_str(s: &str) -> Self;
-}
-
-trait RopeBuilder {
-    type Rope: Rope;
-
-    fn new() -> Self;
-    fn append(self, s: &str) -> Self;
-    fn build(self) -> Self::Rope;
-}
-
-impl Rope for crop::Rope {
-    #[inline]
-    fn from_str(s: &str) -> Self {
-        Self::from(s)
-    }
-}
-
-impl Rope for jumprope::JumpRope {
-    #[inline]
-    fn from_str(s: &str) -> Self {
-        Self::from(s)
-    }
-}
-
-impl Rope for ropey::Rope {
-    #[inline]
-    fn from_str(s: &str) -> Self {
-        Self::from_str(s)
-    }
-}
-
-impl Rope for xi_rope::Rope {
-    #[inline]
-    fn from_str(s: &str) -> Self {
-        Self::from(s)
-    }
-}
-
-impl RopeBuilder for crop::RopeBuilder {
-    type Rope = crop::Rope;
-
-    #[inline]
-    fn new() -> Self {
-        crop::RopeBuilder::new()
-    }
-
-    #[inline]
-    fn append(mut self, s: &str) -> Self {
-        crop::RopeBuilder::append(&mut self, s);
-        self
-    }
-
-    #[inline]
-    fn build(self) -> Self::Rope {
-        self.build()
-    }
-}
-
-impl RopeBuilder for ropey::RopeBuilder {
-    type Rope = ropey::Rope;
-
-    #[inline]
-    fn new() -> Self {
-        ropey::RopeBuilder::new()
-    }
-
-    #[inline]
-    fn append(mut self, s: &str) -> Self {
-        ropey::RopeBuilder::append(&mut self, s);
-        self
-    }
-
-    #[inline]
-    fn build(self) -> Self::Rope {
-        self.finish()
-    }
-}
-
-type XiRopeBuilder = xi_rope::tree::TreeBuilder<xi_rope::RopeInfo>;
-
-impl RopeBuilder for XiRopeBuilder {
-    type Rope = xi_rope::Rope;
-
-    #[inline]
-    fn new() -> Self {
-        XiRopeBuilder::new()
-    }
-
-    #[inline]
-    fn append(mut self, s: &str) -> Self {
-        self.push_str(s);
-        self
-    }
-
-    #[inline]
-    fn build(self) -> Self::Rope {
-        self.build()
-    }
-}
+use rope_benches::gap_buffer::GapBuffer;
+use rope_benches::line_array::LineArray;
+use rope_benches::piece_table::PieceTable;
+use rope_benches::rope::{BuildRope, Operation, Rope, RopeBuilder};
 
 fn bench<F: Fn(&str)>(group: &mut BenchmarkGroup<WallTime>, to_bench: F) {
     group.bench_function("tiny", |bench| bench.iter(|| to_bench(TINY)));
@@ -127,50 +29,24 @@ fn rope_builder<B: RopeBuilder>(s: &str) {
     let _ = b.build();
 }
 
-fn crop_from_str(c: &mut Criterion) {
-    let mut group = c.benchmark_group("crop_from_str");
-    bench(&mut group, from_str::<crop::Rope>);
-}
-
-fn jumprope_from_str(c: &mut Criterion) {
-    let mut group = c.benchmark_group("jumprope_from_str");
-    bench(&mut group, from_str::<jumprope::JumpRope>);
-}
-
-fn ropey_from_str(c: &mut Criterion) {
-    let mut group = c.benchmark_group("ropey_from_str");
-    bench(&mut group, from_str::<ropey::Rope>);
-}
-
-fn xi_rope_from_str(c: &mut Criterion) {
-    let mut group = c.benchmark_group("xi_rope_from_str");
-    bench(&mut group, from_str::<xi_rope::Rope>);
-}
-
-fn crop_builder(c: &mut Criterion) {
-    let mut group = c.benchmark_group("crop_builder");
-    bench(&mut group, rope_builder::<crop::RopeBuilder>);
-}
-
-fn ropey_builder(c: &mut Criterion) {
-    let mut group = c.benchmark_group("ropey_builder");
-    bench(&mut group, rope_builder::<ropey::RopeBuilder>);
-}
-
-fn xi_rope_builder(c: &mut Criterion) {
-    let mut group = c.benchmark_group("xi_rope_builder");
-    bench(&mut group, rope_builder::<XiRopeBuilder>);
+fn bench_rope<R: BuildRope>(c: &mut Criterion) {
+    bench_group::<R>(c, Operation::FromStr, |group| bench(group, from_str::<R>));
+    bench_group::<R>(c, Operation::Build, |group| {
+        bench(group, rope_builder::<R::Builder>)
+    });
 }
 
 criterion_group!(
     benches,
-    crop_from_str,
-    jumprope_from_str,
-    ropey_from_str,
-    xi_rope_from_str,
-    crop_builder,
-    ropey_builder,
-    xi_rope_builder,
+    bench_rope::<String>,
+    bench_rope::<GapBuffer>,
+    bench_rope::<PieceTable>,
+    bench_rope::<LineArray>,
+    bench_rope::<crop::Rope>,
+    bench_rope::<jumprope::JumpRope>,
+    bench_rope::<jumprope::JumpRopeBuf>,
+    bench_rope::<ropey::Rope>,
+    bench_rope::<xi_rope::Rope>,
 );
 
 criterion_main!(benches);
@@ -0,0 +1,75 @@
+use crdt_testdata::TestPatch;
+use criterion::measurement::WallTime;
+use criterion::{
+    criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion, Throughput,
+};
+use rope_benches::cache::CachedTrace;
+use rope_benches::dyn_rope;
+use rope_benches::gap_buffer::GapBuffer;
+use rope_benches::line_array::LineArray;
+use rope_benches::piece_table::PieceTable;
+use rope_benches::rope::Rope;
+use rope_benches::traces;
+
+/// Replays the same trace on `R` called statically and through a
+/// `Box<dyn DynRope>` created from the registry, to measure the overhead of
+/// picking the rope at runtime.
+fn bench<R: Rope + 'static>(group: &mut BenchmarkGroup<WallTime>, name: &str, trace: &CachedTrace) {
+    let trace = trace.get::<R::Offset>();
+
+    group.bench_function(BenchmarkId::new(format!("{name}/static"), R::name()), |b| {
+        b.iter(|| {
+            let mut rope = R::from_str(&trace.start_content);
+            for txn in &trace.txns {
+                for TestPatch(pos, del, ins) in &txn.patches {
+                    rope.replace(*pos..*pos + del, ins);
+                }
+            }
+            rope.len()
+        })
+    });
+
+    // Looking up the rope by name isn't part of the dispatch being measured.
+    let registration = dyn_rope::lookup(R::NAME).unwrap();
+
+    group.bench_function(BenchmarkId::new(format!("{name}/dyn"), R::name()), |b| {
+        b.iter(|| {
+            let mut rope = (registration.from_str)(&trace.start_content);
+            for txn in &trace.txns {
+                for TestPatch(pos, del, ins) in &txn.patches {
+                    rope.replace(*pos..*pos + del, ins);
+                }
+            }
+            rope.len()
+        })
+    });
+}
+
+fn dispatch(c: &mut Criterion) {
+    let trace_files = traces::from_env().unwrap_or_else(|err| {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    });
+
+    let mut group = c.benchmark_group("dispatch");
+
+    for trace_file in &trace_files {
+        let trace = CachedTrace::load(trace_file);
+        let name = trace_file.name.as_str();
+        group.throughput(Throughput::Elements(trace.chars().len() as u64));
+
+        bench::<String>(&mut group, name, &trace);
+        bench::<GapBuffer>(&mut group, name, &trace);
+        bench::<PieceTable>(&mut group, name, &trace);
+        bench::<LineArray>(&mut group, name, &trace);
+        bench::<crop::Rope>(&mut group, name, &trace);
+        bench::<jumprope::JumpRope>(&mut group, name, &trace);
+        bench::<jumprope::JumpRopeBuf>(&mut group, name, &trace);
+        bench::<ropey::Rope>(&mut group, name, &trace);
+        bench::<xi_rope::Rope>(&mut group, name, &trace);
+    }
+}
+
+criterion_group!(benches, dispatch);
+
+criterion_main!(benches);
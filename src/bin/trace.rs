@@ -0,0 +1,313 @@
+//! Tools to create the editing traces replayed by the benchmarks.
+
+use std::error::Error;
+use std::path::Path;
+
+use rope_benches::cache::CachedTrace;
+use rope_benches::export;
+use rope_benches::git;
+use rope_benches::lsp::DocumentLog;
+use rope_benches::metric::{ByteOffset, CharOffset, Edit, Metric, Utf16Offset};
+use rope_benches::recorder::Recorder;
+use rope_benches::stats::TraceStats;
+use rope_benches::synthetic::TypingModel;
+use rope_benches::traces;
+
+const USAGE: &str = "\
+usage:
+    trace record [--offsets bytes|chars|utf16] <start> <edits> <output>
+        Records a trace starting with the contents of <start> to <output>.
+        <edits> has one transaction per line, each a JSON array of
+        [offset, deleted, inserted] patches, with offsets and lengths in
+        chars by default.
+
+    trace generate [--seed <n>] [--fit <trace>] [--size <chars>]
+                   [--locality <p>] [--burst <n>] [--deletes <p>]
+                   [--pastes <p>] [--paste-size <chars>] [--newlines <p>]
+                   <output>
+        Generates a synthetic trace to <output> from a model of typing,
+        starting from the defaults or the parameters fitted from <trace>.
+        Traces written to the traces directory are replayed by the
+        benchmarks like the other ones.
+
+    trace git <repo> <path> <output>
+        Records the history of the file at <path> in the git repository
+        <repo> to <output>, with one transaction per commit changing it.
+
+    trace lsp [--uri <uri>] [--start <file>] [--native <edits>] <log> <output>
+        Records the didChange notifications of a language server log to
+        <output>, for the document at <uri> or the first one in the log.
+        The document starts with the contents of <start>, or with the text
+        of its didOpen notification. <edits> gets a first line with the
+        uri and startContent, then the changes with their LSP ranges, one
+        contentChanges array per line. The trace is in chars, like every
+        trace file; `trace export --offsets` converts it to bytes or UTF-16.
+
+    trace export [--offsets bytes|chars|utf16] [--format json|ndjson]
+                 <trace> <output>
+        Writes a trace, given by name or path, to <output> as plain JSON
+        or as newline-delimited JSON with one transaction per line, with
+        offsets and lengths in chars by default.
+
+    trace stats <trace>...
+        Prints the characteristics of traces, given by name or path.
+";
+
+type Result<T = ()> = std::result::Result<T, Box<dyn Error>>;
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    let result = match args.first().map(String::as_str) {
+        Some("record") => record(&args[1..]),
+        Some("generate") => generate(&args[1..]),
+        Some("git") => import_git(&args[1..]),
+        Some("lsp") => import_lsp(&args[1..]),
+        Some("export") => export(&args[1..]),
+        Some("stats") => stats(&args[1..]),
+        _ => {
+            eprint!("{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn record(args: &[String]) -> Result {
+    let (offsets, args) = take_option(args, "--offsets")?;
+
+    let [start, edits, output] = args.as_slice() else {
+        return Err(format!("expected <start> <edits> <output>\n\n{USAGE}").into());
+    };
+
+    let start = std::fs::read_to_string(start)?;
+    let edits = std::fs::read_to_string(edits)?;
+    let output = Path::new(output);
+
+    let recorder = match offsets.as_deref().unwrap_or("chars") {
+        "bytes" => record_in::<ByteOffset>(&start, &edits)?,
+        "chars" => record_in::<CharOffset>(&start, &edits)?,
+        "utf16" => record_in::<Utf16Offset>(&start, &edits)?,
+        other => return Err(format!("unknown offsets {other:?}").into()),
+    };
+
+    recorder.write(output)?;
+    println!(
+        "recorded {} patches to {}",
+        recorder.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+fn record_in<M: Metric>(start: &str, edits: &str) -> Result<Recorder> {
+    let mut recorder = Recorder::new(start);
+
+    for (idx, line) in edits.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let patches = serde_json::from_str::<Vec<(usize, usize, String)>>(line)
+            .map_err(|err| format!("line {}: {err}", idx + 1))?;
+
+        for (pos, del, ins) in patches {
+            let range = M::new(pos)..M::new(pos + del);
+            recorder.try_edit(&Edit::replace(range, &ins))?;
+        }
+
+        recorder.commit();
+    }
+
+    Ok(recorder)
+}
+
+fn generate(args: &[String]) -> Result {
+    let (seed, args) = parse_option(args, "--seed")?;
+    let (fit, args) = take_option(&args, "--fit")?;
+    let (size, args) = parse_option(&args, "--size")?;
+    let (locality, args) = parse_option(&args, "--locality")?;
+    let (burst, args) = parse_option(&args, "--burst")?;
+    let (deletes, args) = parse_option(&args, "--deletes")?;
+    let (pastes, args) = parse_option(&args, "--pastes")?;
+    let (paste_size, args) = parse_option(&args, "--paste-size")?;
+    let (newlines, args) = parse_option(&args, "--newlines")?;
+
+    let [output] = args.as_slice() else {
+        return Err(format!("expected <output>\n\n{USAGE}").into());
+    };
+    let output = Path::new(output);
+
+    let mut model = match fit {
+        Some(name) => TypingModel::fit(&find_trace(&name)?.load()),
+        None => TypingModel::default(),
+    };
+
+    model.target_len = size.unwrap_or(model.target_len);
+    model.locality = locality.unwrap_or(model.locality);
+    model.burst_len = burst.unwrap_or(model.burst_len);
+    model.delete_ratio = deletes.unwrap_or(model.delete_ratio);
+    model.paste_ratio = pastes.unwrap_or(model.paste_ratio);
+    model.paste_len = paste_size.unwrap_or(model.paste_len);
+    model.newline_ratio = newlines.unwrap_or(model.newline_ratio);
+
+    let recorder = model.generate(seed.unwrap_or(0));
+    recorder.write(output)?;
+    println!(
+        "generated {} patches to {} from {model:?}",
+        recorder.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+fn import_git(args: &[String]) -> Result {
+    let [repo, path, output] = args else {
+        return Err(format!("expected <repo> <path> <output>\n\n{USAGE}").into());
+    };
+    let output = Path::new(output);
+
+    let recorder = git::import(Path::new(repo), path)?;
+    recorder.write(output)?;
+    println!(
+        "recorded {} patches to {}",
+        recorder.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+fn import_lsp(args: &[String]) -> Result {
+    let (uri, args) = take_option(args, "--uri")?;
+    let (start, args) = take_option(&args, "--start")?;
+    let (native, args) = take_option(&args, "--native")?;
+
+    let [log, output] = args.as_slice() else {
+        return Err(format!("expected <log> <output>\n\n{USAGE}").into());
+    };
+    let output = Path::new(output);
+
+    let document = DocumentLog::parse(&std::fs::read_to_string(log)?, uri.as_deref())?;
+    let start = start.map(std::fs::read_to_string).transpose()?;
+
+    let recorder = document.record(start.as_deref());
+    recorder.write(output)?;
+
+    if let Some(native) = native {
+        std::fs::write(native, document.to_native(start.as_deref()))?;
+    }
+
+    println!(
+        "recorded {} patches of {} to {}",
+        recorder.len(),
+        document.uri,
+        output.display()
+    );
+    Ok(())
+}
+
+fn export(args: &[String]) -> Result {
+    let (offsets, args) = take_option(args, "--offsets")?;
+    let (format, args) = take_option(&args, "--format")?;
+
+    let [name, output] = args.as_slice() else {
+        return Err(format!("expected <trace> <output>\n\n{USAGE}").into());
+    };
+
+    let ndjson = match format.as_deref().unwrap_or("json") {
+        "json" => false,
+        "ndjson" => true,
+        other => return Err(format!("unknown format {other:?}").into()),
+    };
+
+    let trace = CachedTrace::load(&find_trace(name)?);
+
+    let exported = match offsets.as_deref().unwrap_or("chars") {
+        "bytes" => export_in::<ByteOffset>(&trace, ndjson),
+        "chars" => export_in::<CharOffset>(&trace, ndjson),
+        "utf16" => export_in::<Utf16Offset>(&trace, ndjson),
+        other => return Err(format!("unknown offsets {other:?}").into()),
+    };
+
+    std::fs::write(output, exported)?;
+    println!("exported {name} to {output}");
+    Ok(())
+}
+
+fn export_in<M: Metric>(trace: &CachedTrace, ndjson: bool) -> String {
+    let trace = trace.get::<M>();
+    match ndjson {
+        true => export::to_ndjson::<M>(&trace),
+        false => export::to_json::<M>(&trace).to_string(),
+    }
+}
+
+fn stats(names: &[String]) -> Result {
+    if names.is_empty() {
+        return Err(format!("expected <trace>...\n\n{USAGE}").into());
+    }
+
+    for name in names {
+        let trace = find_trace(name)?;
+        println!("{}:\n{}", trace.name, TraceStats::new(&trace.load()));
+    }
+    Ok(())
+}
+
+/// Returns the trace with the given name in the traces directory, or the
+/// trace file at that path.
+fn find_trace(name: &str) -> Result<traces::TraceFile> {
+    let path = Path::new(name);
+
+    if path.is_file() {
+        return Ok(traces::TraceFile {
+            name: name.to_owned(),
+            path: path.to_owned(),
+        });
+    }
+
+    let dir = traces::dir_from_env();
+    let found = traces::select(&dir, traces::discover(&dir)?, &[name], &[])?;
+    Ok(found.into_iter().next().unwrap())
+}
+
+/// Removes `--name <value>` from `args`, returning the value if it was
+/// there.
+fn take_option(args: &[String], name: &str) -> Result<(Option<String>, Vec<String>)> {
+    let mut rest = Vec::with_capacity(args.len());
+    let mut value = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg == name {
+            let Some(arg) = args.next() else {
+                return Err(format!("{name} expects a value").into());
+            };
+            value = Some(arg.clone());
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+
+    Ok((value, rest))
+}
+
+/// Removes `--name <value>` from `args`, parsing the value if it was there.
+fn parse_option<T>(args: &[String], name: &str) -> Result<(Option<T>, Vec<String>)>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let (value, rest) = take_option(args, name)?;
+
+    let value = value
+        .map(|value| value.parse())
+        .transpose()
+        .map_err(|err| format!("invalid {name}: {err}"))?;
+
+    Ok((value, rest))
+}
@@ -0,0 +1,250 @@
+//! A wrapper coalescing the edits made to any rope.
+
+use std::borrow::Cow;
+use std::cell::{Ref, RefCell};
+use std::ops::Range;
+
+use crate::metric::Metric;
+use crate::rope::Rope;
+
+/// A rope which holds on to the last edit it was given, merging it with the
+/// following ones while they're adjacent to it, like typing or backspacing
+/// does. The pending edit is only applied to the inner rope once an edit
+/// can't be merged with it, or when the rope is read.
+///
+/// This is a generic version of what [`jumprope::JumpRopeBuf`] does for
+/// [`jumprope::JumpRope`].
+pub struct Buffered<R> {
+    inner: RefCell<Inner<R>>,
+
+    /// The length of the rope once the pending edit is applied.
+    len: usize,
+}
+
+struct Inner<R> {
+    rope: R,
+    pending: Pending,
+
+    /// The text of a pending insertion, kept around between flushes to reuse
+    /// its allocation.
+    text: String,
+}
+
+/// An edit which hasn't been applied to the inner rope yet, in the offsets of
+/// the inner rope.
+enum Pending {
+    None,
+    Insert { at: usize, len: usize },
+    Remove(Range<usize>),
+}
+
+impl<R: Rope> Buffered<R> {
+    #[inline]
+    pub fn new(rope: R) -> Self {
+        Self {
+            len: rope.len(),
+            inner: RefCell::new(Inner {
+                rope,
+                pending: Pending::None,
+                text: String::new(),
+            }),
+        }
+    }
+
+    /// Applies the pending edit and returns the inner rope.
+    #[inline]
+    pub fn borrow(&self) -> Ref<'_, R> {
+        self.inner.borrow_mut().flush();
+        Ref::map(self.inner.borrow(), |inner| &inner.rope)
+    }
+
+    /// Applies the pending edit and returns the inner rope.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        let mut inner = self.inner.into_inner();
+        inner.flush();
+        inner.rope
+    }
+}
+
+impl<R: Rope> Inner<R> {
+    #[inline]
+    fn flush(&mut self) {
+        match std::mem::replace(&mut self.pending, Pending::None) {
+            Pending::None => {}
+            Pending::Insert { at, .. } => {
+                self.rope.insert(at, &self.text);
+                self.text.clear();
+            }
+            Pending::Remove(range) => self.rope.remove(range),
+        }
+    }
+}
+
+impl<R: Rope> Rope for Buffered<R> {
+    const NAME: &'static str = "Buffered";
+
+    type Offset = R::Offset;
+
+    #[inline]
+    fn name() -> Cow<'static, str> {
+        Cow::Owned(format!("Buffered<{}>", R::name()))
+    }
+
+    #[inline]
+    fn from_str(s: &str) -> Self {
+        Self::new(R::from_str(s))
+    }
+
+    fn insert(&mut self, at: usize, s: &str) {
+        let inner = self.inner.get_mut();
+        let len = R::Offset::measure(s).get();
+        self.len += len;
+
+        // Only appending to the pending insertion is merged, anything else
+        // would need to move its text around.
+        if let Pending::Insert {
+            at: start,
+            len: pending_len,
+        } = &mut inner.pending
+        {
+            if at == *start + *pending_len {
+                inner.text.push_str(s);
+                *pending_len += len;
+                return;
+            }
+        }
+
+        inner.flush();
+        inner.text.push_str(s);
+        inner.pending = Pending::Insert { at, len };
+    }
+
+    fn remove(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let inner = self.inner.get_mut();
+        self.len -= range.len();
+
+        match &mut inner.pending {
+            // Removing the end of the pending insertion, like backspacing
+            // right after typing.
+            Pending::Insert { at, len } if range.start >= *at && range.end == *at + *len => {
+                let keep = range.start - *at;
+                let byte_offset = R::Offset::new(keep).to_byte_in(&inner.text);
+                inner.text.truncate(byte_offset);
+                *len = keep;
+
+                if keep == 0 {
+                    inner.pending = Pending::None;
+                }
+                return;
+            }
+
+            // The pending range is still in the offsets of the inner rope,
+            // which hasn't had it removed yet.
+            Pending::Remove(pending) if range.end == pending.start => {
+                pending.start = range.start;
+                return;
+            }
+
+            Pending::Remove(pending) if range.start == pending.start => {
+                pending.end += range.len();
+                return;
+            }
+
+            _ => {}
+        }
+
+        inner.flush();
+        inner.pending = Pending::Remove(range);
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn contents(&self) -> String {
+        self.borrow().contents()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rope::tests::{assert_edits_like_string, random_range, random_text};
+    use crate::synthetic::Rng;
+
+    /// Types and deletes around a cursor which sometimes jumps elsewhere, so
+    /// that most edits get merged with the pending one.
+    #[track_caller]
+    fn assert_typing_like_string<R: Rope>(seed: u64) {
+        let mut rng = Rng(seed);
+        let mut text = String::new();
+        let mut rope = Buffered::new(R::from_str(""));
+        let mut cursor = 0;
+
+        for idx in 0..2000 {
+            if rng.chance(0.05) {
+                cursor = random_range(&mut rng, &text).start;
+            }
+
+            let offset = |byte: usize| R::Offset::measure(&text[..byte]).get();
+
+            match rng.below(4) {
+                // Backspace.
+                0 if cursor > 0 => {
+                    let start = text[..cursor].char_indices().next_back().unwrap().0;
+                    rope.remove(offset(start)..offset(cursor));
+                    text.replace_range(start..cursor, "");
+                    cursor = start;
+                }
+                // Delete.
+                1 if cursor < text.len() => {
+                    let end = cursor + text[cursor..].chars().next().unwrap().len_utf8();
+                    rope.remove(offset(cursor)..offset(end));
+                    text.replace_range(cursor..end, "");
+                }
+                _ => {
+                    let ins = random_text(&mut rng, 2);
+                    rope.insert(offset(cursor), &ins);
+                    text.insert_str(cursor, &ins);
+                    cursor += ins.len();
+                }
+            }
+
+            let name = Buffered::<R>::name();
+            assert_eq!(
+                rope.len(),
+                R::Offset::measure(&text).get(),
+                "{name} len after edit {idx}"
+            );
+
+            if idx % 64 == 0 {
+                assert_eq!(rope.contents(), text, "{name} contents after edit {idx}");
+            }
+        }
+
+        assert_eq!(rope.into_inner().contents(), text);
+    }
+
+    #[test]
+    fn edits_like_string() {
+        for seed in 0..8 {
+            assert_edits_like_string::<Buffered<String>>(seed, |_, _, _| {});
+            assert_edits_like_string::<Buffered<ropey::Rope>>(seed, |_, _, _| {});
+        }
+    }
+
+    #[test]
+    fn typing_like_string() {
+        for seed in 0..8 {
+            assert_typing_like_string::<String>(seed);
+            assert_typing_like_string::<ropey::Rope>(seed);
+        }
+    }
+}
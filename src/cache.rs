@@ -0,0 +1,249 @@
+//! A cache of parsed traces and of their conversions to the metrics of the
+//! ropes, shared by every rope in a run and persisted across runs.
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::UNIX_EPOCH;
+
+use crdt_testdata::{TestData, TestPatch, TestTxn};
+use serde_json::json;
+
+use crate::metric::{translate_trace, CharOffset, Metric};
+use crate::traces::TraceFile;
+
+/// The bytes cache files start with. Changing the encoding has to change
+/// them.
+const MAGIC: &[u8; 8] = b"RBTRACE1";
+
+/// A trace in chars, with its last conversion to another metric.
+pub struct CachedTrace {
+    chars: Rc<TestData>,
+
+    /// The path of the cache files of the trace without their extension, and
+    /// the stamp of its trace file, if it was loaded from one.
+    file: Option<(PathBuf, Stamp)>,
+
+    /// The last conversion asked for. Only one is kept since they take as
+    /// much memory as the trace itself, and ropes in the same metric are
+    /// benchmarked one after the other.
+    converted: RefCell<Option<(&'static str, Rc<TestData>)>>,
+}
+
+/// What the trace files are checked against to tell if the cache files
+/// converted from them are stale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    len: u64,
+    modified: u64,
+}
+
+impl CachedTrace {
+    /// Returns a trace whose conversions are only kept in memory.
+    #[inline]
+    pub fn new(trace: TestData) -> Self {
+        Self {
+            chars: Rc::new(trace),
+            file: None,
+            converted: RefCell::new(None),
+        }
+    }
+
+    /// Loads a trace file, from its cache file if it's up to date.
+    pub fn load(trace_file: &TraceFile) -> Self {
+        let Some(stamp) = Stamp::of(&trace_file.path) else {
+            return Self::new(trace_file.load());
+        };
+
+        // Traces can be given by path, so their name can't be used.
+        let Some(file_name) = trace_file.path.file_name() else {
+            return Self::new(trace_file.load());
+        };
+        let file = cache_dir().join(file_name);
+
+        let chars = read(&file, CharOffset::NAME, stamp).unwrap_or_else(|| {
+            let trace = trace_file.load();
+            write(&file, CharOffset::NAME, stamp, &trace);
+            trace
+        });
+
+        Self {
+            chars: Rc::new(chars),
+            file: Some((file, stamp)),
+            converted: RefCell::new(None),
+        }
+    }
+
+    /// The trace with its offsets in chars, as it's stored in trace files.
+    #[inline]
+    pub fn chars(&self) -> &TestData {
+        &self.chars
+    }
+
+    /// Returns the trace with its offsets in the `M` metric, converting it
+    /// unless it was the last metric asked for.
+    pub fn get<M: Metric>(&self) -> Rc<TestData> {
+        if M::NAME == CharOffset::NAME {
+            return Rc::clone(&self.chars);
+        }
+
+        if let Some((name, trace)) = &*self.converted.borrow() {
+            if *name == M::NAME {
+                return Rc::clone(trace);
+            }
+        }
+
+        // Dropped first so that two conversions are never in memory at once,
+        // unless the previous one is still in use.
+        self.converted.take();
+
+        let cached = self
+            .file
+            .as_ref()
+            .and_then(|(file, stamp)| read(file, M::NAME, *stamp));
+
+        let trace = Rc::new(cached.unwrap_or_else(|| {
+            let trace = translate_trace::<M>(&self.chars);
+            if let Some((file, stamp)) = &self.file {
+                write(file, M::NAME, *stamp, &trace);
+            }
+            trace
+        }));
+
+        *self.converted.borrow_mut() = Some((M::NAME, Rc::clone(&trace)));
+        trace
+    }
+}
+
+impl Stamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+        Some(Self {
+            len: metadata.len(),
+            modified: modified.as_nanos() as u64,
+        })
+    }
+}
+
+/// Returns the directory cache files are written to, in the target
+/// directory.
+pub fn cache_dir() -> PathBuf {
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map_or_else(|| PathBuf::from("target"), PathBuf::from)
+        .join("rope-benches")
+        .join("traces")
+}
+
+/// Returns the path of the cache file of a trace in the given metric, from
+/// the path of its cache files without their extension.
+#[inline]
+fn cache_path(file: &Path, metric: &str) -> PathBuf {
+    let mut path = file.as_os_str().to_owned();
+    path.push(format!(".{metric}.bin"));
+    PathBuf::from(path)
+}
+
+fn read(file: &Path, metric: &str, stamp: Stamp) -> Option<TestData> {
+    let bytes = std::fs::read(cache_path(file, metric)).ok()?;
+    decode(&bytes, stamp)
+}
+
+/// Writes a cache file, ignoring failures since the trace can always be
+/// converted again.
+fn write(file: &Path, metric: &str, stamp: Stamp, trace: &TestData) {
+    let path = cache_path(file, metric);
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let _ = std::fs::write(path, encode(trace, stamp));
+}
+
+/// Encodes a trace as its stamp followed by its contents and patches, with
+/// integers as little-endian `u64`s and strings prefixed by their length.
+fn encode(trace: &TestData, stamp: Stamp) -> Vec<u8> {
+    fn int(bytes: &mut Vec<u8>, n: u64) {
+        bytes.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn string(bytes: &mut Vec<u8>, s: &str) {
+        int(bytes, s.len() as u64);
+        bytes.extend_from_slice(s.as_bytes());
+    }
+
+    let mut bytes = MAGIC.to_vec();
+    int(&mut bytes, stamp.len);
+    int(&mut bytes, stamp.modified);
+    string(&mut bytes, &trace.start_content);
+    string(&mut bytes, &trace.end_content);
+    int(&mut bytes, trace.txns.len() as u64);
+
+    for txn in &trace.txns {
+        int(&mut bytes, txn.patches.len() as u64);
+        for TestPatch(pos, del, ins) in &txn.patches {
+            int(&mut bytes, *pos as u64);
+            int(&mut bytes, *del as u64);
+            string(&mut bytes, ins);
+        }
+    }
+
+    bytes
+}
+
+/// Decodes a trace encoded by [`encode`], or returns `None` if it's invalid
+/// or was encoded from a trace file with another stamp.
+fn decode(bytes: &[u8], stamp: Stamp) -> Option<TestData> {
+    struct Reader<'a>(&'a [u8]);
+
+    impl<'a> Reader<'a> {
+        fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+            let bytes = self.0.get(..len)?;
+            self.0 = &self.0[len..];
+            Some(bytes)
+        }
+
+        fn int(&mut self) -> Option<usize> {
+            let bytes = self.bytes(8)?.try_into().ok()?;
+            usize::try_from(u64::from_le_bytes(bytes)).ok()
+        }
+
+        fn string(&mut self) -> Option<String> {
+            let len = self.int()?;
+            String::from_utf8(self.bytes(len)?.to_vec()).ok()
+        }
+    }
+
+    let mut reader = Reader(bytes);
+
+    if reader.bytes(MAGIC.len())? != MAGIC
+        || reader.int()? as u64 != stamp.len
+        || reader.int()? as u64 != stamp.modified
+    {
+        return None;
+    }
+
+    let start_content = reader.string()?;
+    let end_content = reader.string()?;
+
+    let mut trace = serde_json::from_value::<TestData>(json!({
+        "startContent": start_content,
+        "endContent": end_content,
+        "txns": [],
+    }))
+    .ok()?;
+
+    let txns = reader.int()?;
+    trace.txns.reserve(txns.min(bytes.len()));
+
+    for _ in 0..txns {
+        let len = reader.int()?;
+        let mut patches = Vec::with_capacity(len.min(bytes.len()));
+        for _ in 0..len {
+            patches.push(TestPatch(reader.int()?, reader.int()?, reader.string()?));
+        }
+        trace.txns.push(TestTxn { patches });
+    }
+
+    reader.0.is_empty().then_some(trace)
+}
@@ -0,0 +1,279 @@
+//! Line diffs between two versions of a document, turned into the edits
+//! going from one to the other.
+
+use std::ops::Range;
+
+use crate::metric::{ByteOffset, Edit};
+
+/// The largest number of lines the Myers diff is allowed to add or delete.
+/// Past it, the lines that differ are replaced all at once.
+const MAX_LINE_EDITS: usize = 2048;
+
+/// Returns the edits turning `old` into `new`, in the order they have to be
+/// applied. Lines that differ are diffed with the Myers algorithm, then each
+/// changed hunk is trimmed down to the chars that actually differ.
+pub fn diff(old: &str, new: &str) -> Vec<Edit<ByteOffset>> {
+    let old_lines = old.split_inclusive('\n').collect::<Vec<_>>();
+    let new_lines = new.split_inclusive('\n').collect::<Vec<_>>();
+
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let old_middle = &old_lines[prefix..old_lines.len() - suffix];
+    let new_middle = &new_lines[prefix..new_lines.len() - suffix];
+
+    let hunks = myers(old_middle, new_middle)
+        .unwrap_or_else(|| vec![(0..old_middle.len(), 0..new_middle.len())]);
+
+    let old_offsets = line_offsets(&old_lines);
+    let new_offsets = line_offsets(&new_lines);
+
+    hunks
+        .into_iter()
+        .filter_map(|(old_range, new_range)| {
+            let old_bytes =
+                old_offsets[prefix + old_range.start]..old_offsets[prefix + old_range.end];
+            let new_bytes =
+                new_offsets[prefix + new_range.start]..new_offsets[prefix + new_range.end];
+            trimmed_edit(&old[old_bytes], &new[new_bytes.clone()], new_bytes.start)
+        })
+        .collect()
+}
+
+/// Returns the edit replacing `old` with `new` at `at`, without the chars
+/// they start or end with in common, or `None` if they're equal.
+fn trimmed_edit(old: &str, new: &str, at: usize) -> Option<Edit<ByteOffset>> {
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, old), new)| old != new)
+        .map_or(old.len().min(new.len()), |((idx, _), _)| idx);
+
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+
+    let suffix = old
+        .char_indices()
+        .rev()
+        .zip(new.chars().rev())
+        .take_while(|((_, old), new)| old == new)
+        .last()
+        .map_or(0, |((idx, _), _)| old.len() - idx);
+
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+
+    if old.is_empty() && new.is_empty() {
+        return None;
+    }
+
+    let start = at + prefix;
+    Some(Edit::replace(
+        ByteOffset(start)..ByteOffset(start + old.len()),
+        new,
+    ))
+}
+
+/// Returns the byte offset at which each line starts, followed by the length
+/// of the text.
+fn line_offsets(lines: &[&str]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(lines.len() + 1);
+    let mut offset = 0;
+    offsets.push(offset);
+    for line in lines {
+        offset += line.len();
+        offsets.push(offset);
+    }
+    offsets
+}
+
+/// Returns the ranges of lines of `old` replaced by ranges of lines of `new`,
+/// or `None` if they differ by more than [`MAX_LINE_EDITS`] lines.
+fn myers(old: &[&str], new: &[&str]) -> Option<Vec<(Range<usize>, Range<usize>)>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = (old.len() + new.len()).min(MAX_LINE_EDITS) as isize;
+
+    // `v[k + offset]` is the furthest `x` reached on diagonal `k = x - y`.
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+
+    // The part of `v` which the next round reads, saved before each round to
+    // backtrack through them.
+    let mut rounds = Vec::new();
+
+    'search: {
+        for d in 0..=max {
+            rounds.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+
+            for k in (-d..=d).step_by(2) {
+                let idx = (k + offset) as usize;
+                let mut x = match k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                    true => v[idx + 1],
+                    false => v[idx - 1] + 1,
+                };
+                let mut y = x - k;
+
+                while x < n && y < m && old[x as usize] == new[y as usize] {
+                    x += 1;
+                    y += 1;
+                }
+
+                v[idx] = x;
+
+                if x >= n && y >= m {
+                    break 'search;
+                }
+            }
+        }
+        return None;
+    }
+
+    // The pairs of matching lines, from the last one.
+    let mut matches = Vec::new();
+    let (mut x, mut y) = (n, m);
+
+    for (d, v) in rounds.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let at = |k: isize| v[(k + d + 1) as usize];
+
+        let prev_k = match k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            true => k + 1,
+            false => k - 1,
+        };
+        let prev_x = if d == 0 { 0 } else { at(prev_k) };
+        let prev_y = if d == 0 { 0 } else { prev_x - prev_k };
+
+        // The matching lines moved over after the previous round's edit.
+        let (start_x, start_y) = match d {
+            0 => (0, 0),
+            _ if prev_k == k + 1 => (prev_x, prev_y + 1),
+            _ => (prev_x + 1, prev_y),
+        };
+
+        while x > start_x && y > start_y {
+            x -= 1;
+            y -= 1;
+            matches.push((x as usize, y as usize));
+        }
+
+        x = prev_x;
+        y = prev_y;
+    }
+
+    matches.reverse();
+
+    let mut hunks = Vec::new();
+    let (mut old_start, mut new_start) = (0, 0);
+
+    for (x, y) in matches.into_iter().chain([(old.len(), new.len())]) {
+        if x > old_start || y > new_start {
+            hunks.push((old_start..x, new_start..y));
+        }
+        old_start = x + 1;
+        new_start = y + 1;
+    }
+
+    Some(hunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(old: &str, edits: &[Edit<ByteOffset>]) -> String {
+        let mut text = old.to_owned();
+        for edit in edits {
+            text.replace_range(edit.range.start.0..edit.range.end.0, &edit.text);
+        }
+        text
+    }
+
+    #[track_caller]
+    fn assert_round_trip(old: &str, new: &str) -> Vec<Edit<ByteOffset>> {
+        let edits = diff(old, new);
+        assert_eq!(
+            apply(old, &edits),
+            new,
+            "diff({old:?}, {new:?}) = {edits:?}"
+        );
+        edits
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_round_trip("", "");
+        assert_round_trip("", "a\nb\n");
+        assert_round_trip("a\nb\n", "");
+        assert_round_trip("a\nb\nc\n", "a\nc\n");
+        assert_round_trip("a\nb\nc\n", "a\nx\nb\ny\nc\n");
+        assert_round_trip("a\nb\nc\nd\n", "d\nc\nb\na\n");
+    }
+
+    #[test]
+    fn multi_byte_chars() {
+        assert_round_trip("héllo wörld\n日本語\n", "héllo world\n日本人\n🎉\n");
+        assert_round_trip("🎉🎉\n", "🎉🎊🎉\n");
+        assert_round_trip("e\u{301}\n", "e\u{300}\n");
+
+        let edits = assert_round_trip("aé\n", "aè\n");
+        assert_eq!(edits, [Edit::replace(ByteOffset(1)..ByteOffset(3), "è")]);
+    }
+
+    #[test]
+    fn missing_final_newline() {
+        assert_round_trip("a\nb", "a\nb\n");
+        assert_round_trip("a\nb\n", "a\nb");
+        assert_round_trip("a\nb", "a\nb\nc");
+        assert_round_trip("a\nb", "a\nc");
+        assert_round_trip("a", "b");
+    }
+
+    #[test]
+    fn too_many_line_edits() {
+        let old = (0..MAX_LINE_EDITS)
+            .map(|n| format!("old {n}\n"))
+            .collect::<String>();
+        let new = (0..MAX_LINE_EDITS)
+            .map(|n| format!("new {n}\n"))
+            .collect::<String>();
+
+        let edits = assert_round_trip(&old, &new);
+        assert_eq!(edits.len(), 1);
+
+        assert_round_trip(&format!("start\n{old}end"), &format!("start\n{new}end"));
+    }
+
+    #[test]
+    fn random_round_trips() {
+        // A splitmix64 generator, so that failures can be reproduced.
+        let mut state = 0u64;
+        let mut next = |n: usize| {
+            state = state.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            ((z ^ (z >> 31)) % n as u64) as usize
+        };
+
+        let pieces = ["a", "b", "é", "日", "🎉", "\n", "\n", "\r\n", "ab\n", ""];
+
+        for _ in 0..2000 {
+            let old = (0..next(30))
+                .map(|_| pieces[next(pieces.len())])
+                .collect::<String>();
+            let new = (0..next(30))
+                .map(|_| pieces[next(pieces.len())])
+                .collect::<String>();
+            assert_round_trip(&old, &new);
+        }
+    }
+}
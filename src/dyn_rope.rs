@@ -0,0 +1,130 @@
+//! An object-safe version of [`Rope`] and a registry of the ropes it's
+//! implemented for, to pick the rope to use at runtime.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crdt_testdata::TestData;
+
+use crate::gap_buffer::GapBuffer;
+use crate::line_array::LineArray;
+use crate::metric::{translate_trace, Metric};
+use crate::piece_table::PieceTable;
+use crate::rope::{Operation, Rope};
+
+/// The editing operations of a [`Rope`], callable through a trait object.
+pub trait DynRope {
+    fn name(&self) -> Cow<'static, str>;
+
+    /// The name of the metric the rope's offsets are measured in.
+    fn metric(&self) -> &'static str;
+
+    fn supports(&self, op: Operation) -> bool;
+
+    fn insert(&mut self, at_offset: usize, text: &str);
+
+    fn remove(&mut self, between_offsets: Range<usize>);
+
+    fn replace(&mut self, between_offsets: Range<usize>, text: &str);
+
+    fn len(&self) -> usize;
+
+    fn contents(&self) -> String;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<R: Rope> DynRope for R {
+    #[inline]
+    fn name(&self) -> Cow<'static, str> {
+        R::name()
+    }
+
+    #[inline]
+    fn metric(&self) -> &'static str {
+        R::Offset::NAME
+    }
+
+    #[inline]
+    fn supports(&self, op: Operation) -> bool {
+        R::supports(op)
+    }
+
+    #[inline]
+    fn insert(&mut self, at: usize, text: &str) {
+        Rope::insert(self, at, text);
+    }
+
+    #[inline]
+    fn remove(&mut self, range: Range<usize>) {
+        Rope::remove(self, range);
+    }
+
+    #[inline]
+    fn replace(&mut self, range: Range<usize>, text: &str) {
+        Rope::replace(self, range, text);
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        Rope::len(self)
+    }
+
+    #[inline]
+    fn contents(&self) -> String {
+        Rope::contents(self)
+    }
+}
+
+/// A rope which can be created by name.
+#[derive(Clone, Copy)]
+pub struct Registration {
+    /// The [`Rope::NAME`] of the rope.
+    pub name: &'static str,
+
+    pub from_str: fn(&str) -> Box<dyn DynRope>,
+
+    /// Translates a trace into the offsets the rope is addressed with.
+    pub translate_trace: fn(&TestData) -> TestData,
+}
+
+impl Registration {
+    pub const fn new<R: Rope + 'static>() -> Self {
+        Self {
+            name: R::NAME,
+            from_str: boxed::<R>,
+            translate_trace: translate_trace::<R::Offset>,
+        }
+    }
+}
+
+fn boxed<R: Rope + 'static>(s: &str) -> Box<dyn DynRope> {
+    Box::new(R::from_str(s))
+}
+
+/// Every rope that can be created by name.
+pub const ROPES: &[Registration] = &[
+    Registration::new::<String>(),
+    Registration::new::<GapBuffer>(),
+    Registration::new::<PieceTable>(),
+    Registration::new::<LineArray>(),
+    Registration::new::<crop::Rope>(),
+    Registration::new::<jumprope::JumpRope>(),
+    Registration::new::<jumprope::JumpRopeBuf>(),
+    Registration::new::<ropey::Rope>(),
+    Registration::new::<xi_rope::Rope>(),
+];
+
+/// Returns the rope registered under `name`, ignoring case.
+pub fn lookup(name: &str) -> Option<&'static Registration> {
+    ROPES
+        .iter()
+        .find(|registration| registration.name.eq_ignore_ascii_case(name))
+}
+
+/// Creates the rope registered under `name` from `s`.
+pub fn from_str(name: &str, s: &str) -> Option<Box<dyn DynRope>> {
+    lookup(name).map(|registration| (registration.from_str)(s))
+}
@@ -0,0 +1,52 @@
+//! Export of traces to plain formats, so that ropes written in other
+//! languages can replay exactly the same edits.
+
+use crdt_testdata::{TestData, TestPatch};
+use serde_json::{json, Value};
+
+use crate::metric::Metric;
+
+/// Returns a trace whose offsets are in the `M` metric in the JSON format of
+/// trace files, with an extra `offsets` field naming the metric.
+pub fn to_json<M: Metric>(trace: &TestData) -> Value {
+    let txns = trace
+        .txns
+        .iter()
+        .map(|txn| json!({ "patches": txn.patches.iter().map(patch).collect::<Vec<_>>() }))
+        .collect::<Vec<_>>();
+
+    json!({
+        "offsets": M::NAME,
+        "startContent": trace.start_content,
+        "endContent": trace.end_content,
+        "txns": txns,
+    })
+}
+
+/// Returns a trace whose offsets are in the `M` metric as newline-delimited
+/// JSON: a first line with the `offsets`, `startContent` and `endContent`
+/// fields, then one line per transaction with its array of
+/// `[offset, deleted, inserted]` patches.
+pub fn to_ndjson<M: Metric>(trace: &TestData) -> String {
+    let header = json!({
+        "offsets": M::NAME,
+        "startContent": trace.start_content,
+        "endContent": trace.end_content,
+    });
+
+    let mut ndjson = header.to_string();
+    ndjson.push('\n');
+
+    for txn in &trace.txns {
+        let patches = txn.patches.iter().map(patch).collect::<Vec<_>>();
+        ndjson.push_str(&Value::from(patches).to_string());
+        ndjson.push('\n');
+    }
+
+    ndjson
+}
+
+#[inline]
+fn patch(TestPatch(pos, del, ins): &TestPatch) -> Value {
+    json!([pos, del, ins])
+}
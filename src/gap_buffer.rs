@@ -0,0 +1,403 @@
+//! A gap buffer, used as a baseline for the ropes.
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::metric::ByteOffset;
+use crate::rope::{BuildRope, IterRope, Rope, RopeBuilder, SliceRope};
+
+/// The minimum number of bytes the gap is grown by when it fills up.
+const MIN_GAP: usize = 1024;
+
+/// A text buffer made of a single allocation with a gap at the cursor.
+/// Edits move the gap to where they happen, so a run of edits close to each
+/// other only needs to copy the bytes between them.
+#[derive(Clone, Default)]
+pub struct GapBuffer {
+    buf: Vec<u8>,
+    gap: Range<usize>,
+}
+
+/// A byte range of a [`GapBuffer`], made of the text before and after the
+/// gap.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GapSlice<'a> {
+    first: &'a str,
+    second: &'a str,
+}
+
+impl GapBuffer {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buf.len() - self.gap.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> GapSlice<'_> {
+        // SAFETY: the buffer only ever contains text copied from `&str`s, and
+        // the gap always starts and ends on char boundaries since we check
+        // that every edit does.
+        unsafe {
+            GapSlice {
+                first: std::str::from_utf8_unchecked(&self.buf[..self.gap.start]),
+                second: std::str::from_utf8_unchecked(&self.buf[self.gap.end..]),
+            }
+        }
+    }
+
+    pub fn insert(&mut self, at: usize, text: &str) {
+        assert!(self.is_char_boundary(at));
+
+        if text.len() > self.gap.len() {
+            self.grow_gap(text.len());
+        }
+
+        self.move_gap(at);
+        self.buf[at..at + text.len()].copy_from_slice(text.as_bytes());
+        self.gap.start += text.len();
+    }
+
+    pub fn remove(&mut self, range: Range<usize>) {
+        assert!(range.start <= range.end && range.end <= self.len());
+        assert!(self.is_char_boundary(range.start));
+        assert!(self.is_char_boundary(range.end));
+
+        self.move_gap(range.start);
+        self.gap.end += range.len();
+    }
+
+    #[inline]
+    fn is_char_boundary(&self, offset: usize) -> bool {
+        match offset.cmp(&self.gap.start) {
+            std::cmp::Ordering::Less => (self.buf[offset] as i8) >= -0x40,
+            _ => {
+                let offset = offset + self.gap.len();
+                offset == self.buf.len() || (self.buf[offset] as i8) >= -0x40
+            }
+        }
+    }
+
+    /// Moves the start of the gap to the given byte offset.
+    #[inline]
+    fn move_gap(&mut self, to: usize) {
+        let Range { start, end } = self.gap;
+
+        if to < start {
+            let moved = start - to;
+            self.buf.copy_within(to..start, end - moved);
+            self.gap = to..end - moved;
+        } else if to > start {
+            let moved = to - start;
+            self.buf.copy_within(end..end + moved, start);
+            self.gap = to..end + moved;
+        }
+    }
+
+    /// Grows the gap so that it can fit at least `additional` more bytes.
+    fn grow_gap(&mut self, additional: usize) {
+        let grow_by = additional.max(self.buf.len()).max(MIN_GAP);
+        let end = self.gap.end;
+        self.buf.splice(end..end, std::iter::repeat_n(0, grow_by));
+        self.gap.end += grow_by;
+    }
+}
+
+impl From<&str> for GapBuffer {
+    #[inline]
+    fn from(s: &str) -> Self {
+        let mut buf = Vec::with_capacity(s.len() + MIN_GAP);
+        buf.extend_from_slice(s.as_bytes());
+        buf.resize(s.len() + MIN_GAP, 0);
+        Self {
+            buf,
+            gap: s.len()..s.len() + MIN_GAP,
+        }
+    }
+}
+
+impl From<GapSlice<'_>> for GapBuffer {
+    #[inline]
+    fn from(s: GapSlice<'_>) -> Self {
+        let mut buffer = Self::from(s.first);
+        buffer.insert(s.first.len(), s.second);
+        buffer
+    }
+}
+
+impl fmt::Debug for GapBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("GapBuffer").field(&self.as_slice()).finish()
+    }
+}
+
+impl fmt::Display for GapBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.as_slice(), f)
+    }
+}
+
+impl<'a> GapSlice<'a> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.first.len() + self.second.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn byte_slice(&self, range: Range<usize>) -> Self {
+        let split = self.first.len();
+        Self {
+            first: &self.first[range.start.min(split)..range.end.min(split)],
+            second: &self.second
+                [range.start.saturating_sub(split)..range.end.saturating_sub(split)],
+        }
+    }
+
+    #[inline]
+    pub fn line_len(&self) -> usize {
+        self.lines().count()
+    }
+
+    #[inline]
+    pub fn line_slice(&self, range: Range<usize>) -> Self {
+        let start = self.byte_of_line(range.start);
+        let end = start + self.byte_slice(start..self.len()).byte_of_line(range.len());
+        self.byte_slice(start..end)
+    }
+
+    /// Returns the byte offset of the start of the given line, or the length
+    /// of the slice if it has fewer lines than that.
+    #[inline]
+    pub fn byte_of_line(&self, line: usize) -> usize {
+        if line == 0 {
+            return 0;
+        }
+        let first = self.first.match_indices('\n').map(|(idx, _)| idx);
+        let second = self
+            .second
+            .match_indices('\n')
+            .map(|(idx, _)| self.first.len() + idx);
+        first
+            .chain(second)
+            .nth(line - 1)
+            .map_or(self.len(), |idx| idx + 1)
+    }
+
+    #[inline]
+    pub fn chunks(&self) -> std::array::IntoIter<&'a str, 2> {
+        [self.first, self.second].into_iter()
+    }
+
+    #[inline]
+    pub fn bytes(&self) -> std::iter::Chain<std::str::Bytes<'a>, std::str::Bytes<'a>> {
+        self.first.bytes().chain(self.second.bytes())
+    }
+
+    #[inline]
+    pub fn chars(&self) -> std::iter::Chain<std::str::Chars<'a>, std::str::Chars<'a>> {
+        self.first.chars().chain(self.second.chars())
+    }
+
+    #[inline]
+    pub fn lines(&self) -> GapLines<'a> {
+        GapLines { rest: *self }
+    }
+
+    #[inline]
+    fn find_newline(&self) -> Option<usize> {
+        self.first
+            .find('\n')
+            .or_else(|| self.second.find('\n').map(|idx| self.first.len() + idx))
+    }
+}
+
+impl fmt::Display for GapSlice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.first)?;
+        f.write_str(self.second)
+    }
+}
+
+/// An iterator over the lines of a [`GapSlice`], not including the line
+/// breaks.
+#[derive(Clone)]
+pub struct GapLines<'a> {
+    rest: GapSlice<'a>,
+}
+
+impl<'a> Iterator for GapLines<'a> {
+    type Item = GapSlice<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let line = match self.rest.find_newline() {
+            Some(newline) => {
+                let line = self.rest.byte_slice(0..newline);
+                self.rest = self.rest.byte_slice(newline + 1..self.rest.len());
+                line
+            }
+            None => std::mem::take(&mut self.rest),
+        };
+
+        Some(line)
+    }
+}
+
+impl Rope for GapBuffer {
+    const NAME: &'static str = "GapBuffer";
+
+    type Offset = ByteOffset;
+
+    #[inline(always)]
+    fn from_str(s: &str) -> Self {
+        s.into()
+    }
+
+    #[inline(always)]
+    fn insert(&mut self, at: usize, s: &str) {
+        self.insert(at, s);
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, range: Range<usize>) {
+        self.remove(range);
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn contents(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SliceRope for GapBuffer {
+    type Slice<'a> = GapSlice<'a>;
+
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn byte_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        self.as_slice().byte_slice(range)
+    }
+
+    #[inline]
+    fn line_len(&self) -> usize {
+        self.as_slice().line_len()
+    }
+
+    #[inline]
+    fn line_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        self.as_slice().line_slice(range)
+    }
+
+    #[inline]
+    fn from_slice(s: Self::Slice<'_>) -> Self {
+        Self::from(s)
+    }
+}
+
+impl IterRope for GapBuffer {
+    type Chunks<'a> = std::array::IntoIter<&'a str, 2>;
+    type Bytes<'a> = std::iter::Chain<std::str::Bytes<'a>, std::str::Bytes<'a>>;
+    type Chars<'a> = std::iter::Chain<std::str::Chars<'a>, std::str::Chars<'a>>;
+    type Line<'a> = GapSlice<'a>;
+    type Lines<'a> = GapLines<'a>;
+
+    #[inline]
+    fn chunks(&self) -> Self::Chunks<'_> {
+        self.as_slice().chunks()
+    }
+
+    #[inline]
+    fn bytes(&self) -> Self::Bytes<'_> {
+        self.as_slice().bytes()
+    }
+
+    #[inline]
+    fn chars(&self) -> Self::Chars<'_> {
+        self.as_slice().chars()
+    }
+
+    #[inline]
+    fn lines(&self) -> Self::Lines<'_> {
+        self.as_slice().lines()
+    }
+}
+
+impl BuildRope for GapBuffer {
+    type Builder = GapBuffer;
+}
+
+impl RopeBuilder for GapBuffer {
+    type Rope = GapBuffer;
+
+    #[inline]
+    fn new() -> Self {
+        GapBuffer::new()
+    }
+
+    #[inline]
+    fn append(mut self, s: &str) -> Self {
+        self.insert(self.len(), s);
+        self
+    }
+
+    #[inline]
+    fn build(self) -> Self::Rope {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rope::tests::{assert_edits_like_string, random_line_range, random_range};
+
+    #[test]
+    fn edits_like_string() {
+        for seed in 0..8 {
+            assert_edits_like_string::<GapBuffer>(seed, |buffer, text, rng| {
+                let range = random_range(rng, text);
+                let slice = buffer.byte_slice(range.clone());
+                assert_eq!(slice.to_string(), text[range.clone()]);
+                assert!(slice.chars().eq(text[range.clone()].chars()));
+                assert!(slice
+                    .lines()
+                    .map(|line| line.to_string())
+                    .eq(text[range].split_terminator('\n')));
+
+                let lines = random_line_range(rng, text);
+                assert_eq!(
+                    buffer.line_slice(lines.clone()).to_string(),
+                    SliceRope::line_slice(text, lines)
+                );
+                assert_eq!(buffer.line_len(), text.lines().count());
+            });
+        }
+    }
+}
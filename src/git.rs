@@ -0,0 +1,71 @@
+//! Import of the history of a file in a git repository as an editing trace.
+
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use crate::diff::diff;
+use crate::recorder::Recorder;
+
+/// Records the history of the file at `path` in the git repository at
+/// `repo`, from an empty document. Each commit changing the file is a
+/// transaction made of the edits between its version of the file and the
+/// previous one. Renames aren't followed.
+pub fn import(repo: &Path, path: &str) -> io::Result<Recorder> {
+    let log = git(repo, &["log", "--reverse", "--format=%H", "--", path])?;
+    let commits = String::from_utf8_lossy(&log);
+
+    if commits.trim().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no commits change {path} in {}", repo.display()),
+        ));
+    }
+
+    let deleted = git(repo, &["log", "--diff-filter=D", "--format=%H", "--", path])?;
+    let deleted = String::from_utf8_lossy(&deleted);
+
+    let mut recorder = Recorder::new("");
+    let mut previous = String::new();
+
+    for commit in commits.lines() {
+        // The file doesn't exist in the commits deleting it.
+        let contents = match deleted.lines().any(|deleting| deleting == commit) {
+            true => String::new(),
+            // `./` makes git resolve the path from `repo` like `git log`,
+            // instead of from the root of the work tree.
+            false => {
+                let contents = git(repo, &["show", &format!("{commit}:./{path}")])?;
+                String::from_utf8_lossy(&contents).into_owned()
+            }
+        };
+
+        for edit in diff(&previous, &contents) {
+            recorder.edit(&edit);
+        }
+        recorder.commit();
+
+        previous = contents;
+    }
+
+    Ok(recorder)
+}
+
+/// Runs git in `repo`, returning its output.
+fn git(repo: &Path, args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(output.stdout)
+}
@@ -0,0 +1,362 @@
+//! A wrapper logging the operations performed on any rope, used to check
+//! what the benchmark workloads actually exercise.
+
+use std::borrow::Cow;
+use std::cell::{Ref, RefCell};
+use std::fmt;
+use std::ops::Range;
+
+use crate::metric::Metric;
+use crate::rope::{BuildRope, IterRope, Operation, Rope, RopeBuilder, SliceRope};
+
+/// The environment variable which makes the benchmarks print a [`Summary`]
+/// of each of their workloads before measuring it.
+pub const ENV_VAR: &str = "ROPE_BENCHES_INSTRUMENT";
+
+/// Returns whether the benchmarks should print a summary of their workloads.
+#[inline]
+pub fn enabled() -> bool {
+    std::env::var_os(ENV_VAR).is_some()
+}
+
+/// A call made to an [`Instrumented`] rope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Call {
+    pub op: Operation,
+
+    /// The start of the range the call operates on, in the offsets of the
+    /// rope for edits, in bytes for byte slices and in lines for line slices.
+    pub offset: usize,
+
+    /// The length of that range, or of the whole document for operations
+    /// which don't take a range.
+    pub len: usize,
+
+    /// The number of bytes inserted by the call.
+    pub inserted: usize,
+
+    /// The length of the document before the call, in the offsets of the
+    /// rope.
+    pub doc_len: usize,
+}
+
+/// A rope recording every call made to it.
+pub struct Instrumented<R> {
+    rope: R,
+    log: RefCell<Vec<Call>>,
+}
+
+impl<R: Rope> Instrumented<R> {
+    #[inline]
+    fn new(rope: R, call: Call) -> Self {
+        Self {
+            rope,
+            log: RefCell::new(vec![call]),
+        }
+    }
+
+    #[inline]
+    fn record(&self, op: Operation, range: Range<usize>, inserted: usize) {
+        self.log.borrow_mut().push(Call {
+            op,
+            offset: range.start,
+            len: range.len(),
+            inserted,
+            doc_len: self.rope.len(),
+        });
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &R {
+        &self.rope
+    }
+
+    #[inline]
+    pub fn log(&self) -> Ref<'_, [Call]> {
+        Ref::map(self.log.borrow(), Vec::as_slice)
+    }
+
+    pub fn summary(&self) -> Summary {
+        Summary::new::<R::Offset>(&self.log())
+    }
+}
+
+/// Aggregate statistics over the calls made to an [`Instrumented`] rope.
+#[derive(Debug, Clone)]
+pub struct Summary {
+    metric: &'static str,
+
+    /// The statistics of each operation, in the order they were first called.
+    pub ops: Vec<OpSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpSummary {
+    pub op: Operation,
+    pub calls: usize,
+
+    /// The total length of the ranges the operation was called with.
+    pub len: usize,
+
+    /// The total number of bytes inserted by the operation.
+    pub inserted: usize,
+
+    /// The smallest and largest document the operation was called on.
+    pub doc_len: Range<usize>,
+}
+
+impl Summary {
+    pub fn new<M: Metric>(log: &[Call]) -> Self {
+        let mut ops = Vec::<OpSummary>::new();
+
+        for call in log {
+            match ops.iter_mut().find(|summary| summary.op == call.op) {
+                Some(summary) => {
+                    summary.calls += 1;
+                    summary.len += call.len;
+                    summary.inserted += call.inserted;
+                    summary.doc_len.start = summary.doc_len.start.min(call.doc_len);
+                    summary.doc_len.end = summary.doc_len.end.max(call.doc_len);
+                }
+                None => ops.push(OpSummary {
+                    op: call.op,
+                    calls: 1,
+                    len: call.len,
+                    inserted: call.inserted,
+                    doc_len: call.doc_len..call.doc_len,
+                }),
+            }
+        }
+
+        Self {
+            metric: M::NAME,
+            ops,
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for summary in &self.ops {
+            writeln!(
+                f,
+                "  {}: {} calls, total range length {}, {} bytes inserted, document of {}..={} {}",
+                summary.op,
+                summary.calls,
+                summary.len,
+                summary.inserted,
+                summary.doc_len.start,
+                summary.doc_len.end,
+                self.metric,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Rope> Rope for Instrumented<R> {
+    const NAME: &'static str = "Instrumented";
+
+    const UNSUPPORTED: &'static [Operation] = R::UNSUPPORTED;
+
+    type Offset = R::Offset;
+
+    #[inline]
+    fn name() -> Cow<'static, str> {
+        Cow::Owned(format!("Instrumented<{}>", R::name()))
+    }
+
+    #[inline]
+    fn from_str(s: &str) -> Self {
+        let rope = R::from_str(s);
+        let len = rope.len();
+        Self::new(
+            rope,
+            Call {
+                op: Operation::FromStr,
+                offset: 0,
+                len,
+                inserted: s.len(),
+                doc_len: 0,
+            },
+        )
+    }
+
+    #[inline]
+    fn insert(&mut self, at: usize, s: &str) {
+        self.record(Operation::Insert, at..at, s.len());
+        self.rope.insert(at, s);
+    }
+
+    #[inline]
+    fn remove(&mut self, range: Range<usize>) {
+        self.record(Operation::Remove, range.clone(), 0);
+        self.rope.remove(range);
+    }
+
+    #[inline]
+    fn replace(&mut self, range: Range<usize>, s: &str) {
+        // Traces replay every patch through `replace`, so calls are recorded
+        // by what they do.
+        let op = match (range.is_empty(), s.is_empty()) {
+            (true, _) => Operation::Insert,
+            (false, true) => Operation::Remove,
+            (false, false) => Operation::Replace,
+        };
+        self.record(op, range.clone(), s.len());
+        self.rope.replace(range, s);
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    #[inline]
+    fn contents(&self) -> String {
+        self.rope.contents()
+    }
+}
+
+impl<R: SliceRope> SliceRope for Instrumented<R> {
+    type Slice<'a>
+        = R::Slice<'a>
+    where
+        Self: 'a;
+
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.rope.byte_len()
+    }
+
+    #[inline]
+    fn byte_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        self.record(Operation::ByteSlice, range.clone(), 0);
+        self.rope.byte_slice(range)
+    }
+
+    #[inline]
+    fn line_len(&self) -> usize {
+        self.rope.line_len()
+    }
+
+    #[inline]
+    fn line_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        self.record(Operation::LineSlice, range.clone(), 0);
+        self.rope.line_slice(range)
+    }
+
+    #[inline]
+    fn from_slice(s: Self::Slice<'_>) -> Self {
+        let rope = R::from_slice(s);
+        let len = rope.len();
+        Self::new(
+            rope,
+            Call {
+                op: Operation::FromSlice,
+                offset: 0,
+                len,
+                inserted: 0,
+                doc_len: 0,
+            },
+        )
+    }
+}
+
+impl<R: IterRope> IterRope for Instrumented<R> {
+    type Chunks<'a>
+        = R::Chunks<'a>
+    where
+        Self: 'a;
+
+    type Bytes<'a>
+        = R::Bytes<'a>
+    where
+        Self: 'a;
+
+    type Chars<'a>
+        = R::Chars<'a>
+    where
+        Self: 'a;
+
+    type Line<'a>
+        = R::Line<'a>
+    where
+        Self: 'a;
+
+    type Lines<'a>
+        = R::Lines<'a>
+    where
+        Self: 'a;
+
+    #[inline]
+    fn chunks(&self) -> Self::Chunks<'_> {
+        self.record(Operation::IterChunks, 0..self.rope.len(), 0);
+        self.rope.chunks()
+    }
+
+    #[inline]
+    fn bytes(&self) -> Self::Bytes<'_> {
+        self.record(Operation::IterBytes, 0..self.rope.len(), 0);
+        self.rope.bytes()
+    }
+
+    #[inline]
+    fn chars(&self) -> Self::Chars<'_> {
+        self.record(Operation::IterChars, 0..self.rope.len(), 0);
+        self.rope.chars()
+    }
+
+    #[inline]
+    fn lines(&self) -> Self::Lines<'_> {
+        self.record(Operation::IterLines, 0..self.rope.len(), 0);
+        self.rope.lines()
+    }
+}
+
+impl<R: BuildRope> BuildRope for Instrumented<R> {
+    type Builder = InstrumentedBuilder<R>;
+}
+
+/// The builder of an [`Instrumented`] rope, which records a single
+/// [`Operation::Build`] call once it's done.
+pub struct InstrumentedBuilder<R: BuildRope> {
+    builder: R::Builder,
+    appended: usize,
+}
+
+impl<R: BuildRope> RopeBuilder for InstrumentedBuilder<R> {
+    type Rope = Instrumented<R>;
+
+    #[inline]
+    fn new() -> Self {
+        Self {
+            builder: R::Builder::new(),
+            appended: 0,
+        }
+    }
+
+    #[inline]
+    fn append(self, s: &str) -> Self {
+        Self {
+            builder: self.builder.append(s),
+            appended: self.appended + s.len(),
+        }
+    }
+
+    #[inline]
+    fn build(self) -> Self::Rope {
+        let rope = self.builder.build();
+        let len = rope.len();
+        Instrumented::new(
+            rope,
+            Call {
+                op: Operation::Build,
+                offset: 0,
+                len,
+                inserted: self.appended,
+                doc_len: 0,
+            },
+        )
+    }
+}
@@ -0,0 +1,58 @@
+//! Inversion of traces, turning the insertion-heavy traces we have into
+//! deletion-heavy ones.
+
+use crdt_testdata::{TestData, TestPatch, TestTxn};
+
+/// The environment variable which, when set, also replays the inverse of
+/// every trace.
+pub const ENV_VAR: &str = "ROPE_BENCHES_INVERT";
+
+#[inline]
+pub fn enabled() -> bool {
+    std::env::var_os(ENV_VAR).is_some()
+}
+
+/// Returns the trace undoing `trace`: it starts at its end contents and
+/// applies the inverse of its patches in reverse order, ending at its start
+/// contents.
+///
+/// # Panics
+///
+/// Panics if replaying `trace` doesn't end at its end contents.
+pub fn invert(trace: &TestData) -> TestData {
+    let mut text = ropey::Rope::from_str(&trace.start_content);
+
+    // Replay the trace to capture the text deleted by each patch.
+    let mut txns = trace
+        .txns
+        .iter()
+        .map(|txn| {
+            let mut patches = txn
+                .patches
+                .iter()
+                .map(|TestPatch(pos, del, ins)| {
+                    let deleted = text.slice(*pos..pos + del).to_string();
+                    text.remove(*pos..pos + del);
+                    text.insert(*pos, ins);
+                    TestPatch(*pos, ins.chars().count(), deleted)
+                })
+                .collect::<Vec<_>>();
+
+            patches.reverse();
+            TestTxn { patches }
+        })
+        .collect::<Vec<_>>();
+
+    assert!(
+        text == trace.end_content.as_str(),
+        "replaying the trace doesn't end at its end contents"
+    );
+
+    txns.reverse();
+
+    let mut inverted = trace.clone();
+    inverted.start_content = trace.end_content.clone();
+    inverted.end_content = trace.start_content.clone();
+    inverted.txns = txns;
+    inverted
+}
@@ -0,0 +1,174 @@
+//! Timing of the individual patches of a trace, to compare the tail latency
+//! of the ropes and not just their throughput.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crdt_testdata::{TestData, TestPatch};
+
+use crate::rope::Rope;
+
+/// The environment variable which makes the trace benchmarks print the
+/// latency distribution of each rope on each trace before measuring it.
+pub const ENV_VAR: &str = "ROPE_BENCHES_LATENCY";
+
+/// The number of times a trace is replayed to collect latencies.
+pub const RUNS: usize = 5;
+
+/// Returns whether the trace benchmarks should print latency distributions.
+#[inline]
+pub fn enabled() -> bool {
+    std::env::var_os(ENV_VAR).is_some()
+}
+
+/// The kind of edit a patch performs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchKind {
+    Insert,
+    Delete,
+    Replace,
+}
+
+impl PatchKind {
+    #[inline]
+    pub fn of(patch: &TestPatch) -> Self {
+        match (patch.1, patch.2.is_empty()) {
+            (0, _) => Self::Insert,
+            (_, true) => Self::Delete,
+            (_, false) => Self::Replace,
+        }
+    }
+}
+
+impl fmt::Display for PatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Insert => "insert",
+            Self::Delete => "delete",
+            Self::Replace => "replace",
+        })
+    }
+}
+
+/// The time taken by every patch of one or more replays, by kind of patch.
+#[derive(Debug, Clone, Default)]
+pub struct Latencies {
+    pub insert: Vec<Duration>,
+    pub delete: Vec<Duration>,
+    pub replace: Vec<Duration>,
+}
+
+impl Latencies {
+    #[inline]
+    pub fn of(&self, kind: PatchKind) -> &[Duration] {
+        match kind {
+            PatchKind::Insert => &self.insert,
+            PatchKind::Delete => &self.delete,
+            PatchKind::Replace => &self.replace,
+        }
+    }
+
+    #[inline]
+    fn of_mut(&mut self, kind: PatchKind) -> &mut Vec<Duration> {
+        match kind {
+            PatchKind::Insert => &mut self.insert,
+            PatchKind::Delete => &mut self.delete,
+            PatchKind::Replace => &mut self.replace,
+        }
+    }
+
+    pub fn percentiles(&self) -> Vec<(PatchKind, Percentiles)> {
+        [PatchKind::Insert, PatchKind::Delete, PatchKind::Replace]
+            .into_iter()
+            .filter_map(|kind| Some((kind, Percentiles::new(self.of(kind))?)))
+            .collect()
+    }
+}
+
+impl fmt::Display for Latencies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (kind, percentiles) in self.percentiles() {
+            writeln!(f, "  {kind}: {percentiles}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Replays `trace`, which has to be in the offsets of `R`, timing each patch.
+pub fn replay<R: Rope>(trace: &TestData, latencies: &mut Latencies) {
+    let mut rope = R::from_str(&trace.start_content);
+
+    for txn in &trace.txns {
+        for patch in &txn.patches {
+            let TestPatch(pos, del, ins) = patch;
+            let kind = PatchKind::of(patch);
+
+            let start = Instant::now();
+            match kind {
+                PatchKind::Insert => rope.insert(*pos, ins),
+                PatchKind::Delete => rope.remove(*pos..*pos + del),
+                PatchKind::Replace => rope.replace(*pos..*pos + del, ins),
+            }
+            let elapsed = start.elapsed();
+
+            latencies.of_mut(kind).push(elapsed);
+        }
+    }
+}
+
+/// Replays `trace` [`RUNS`] times, timing each patch.
+pub fn measure<R: Rope>(trace: &TestData) -> Latencies {
+    let mut latencies = Latencies::default();
+    for _ in 0..RUNS {
+        replay::<R>(trace, &mut latencies);
+    }
+    latencies
+}
+
+/// A summary of a latency distribution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Percentiles {
+    pub count: usize,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub p999: Duration,
+    pub max: Duration,
+}
+
+impl Percentiles {
+    /// Returns the percentiles of `samples`, or `None` if there are none.
+    pub fn new(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        // The nearest-rank percentile.
+        let at = |percentile: f64| {
+            let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+
+        Some(Self {
+            count: sorted.len(),
+            p50: at(50.0),
+            p90: at(90.0),
+            p99: at(99.0),
+            p999: at(99.9),
+            max: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+impl fmt::Display for Percentiles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} patches, p50 {:?}, p90 {:?}, p99 {:?}, p99.9 {:?}, max {:?}",
+            self.count, self.p50, self.p90, self.p99, self.p999, self.max,
+        )
+    }
+}
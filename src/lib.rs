@@ -0,0 +1,24 @@
+pub mod buffered;
+pub mod cache;
+pub mod diff;
+pub mod dyn_rope;
+pub mod export;
+pub mod gap_buffer;
+pub mod git;
+pub mod instrumented;
+pub mod inverse;
+pub mod latency;
+pub mod line_array;
+pub mod lsp;
+pub mod metric;
+pub mod piece_table;
+pub mod recorder;
+pub mod rope;
+pub mod scaling;
+pub mod stats;
+pub mod synthetic;
+pub mod traces;
+pub mod unicode;
+pub mod utf16;
+pub mod verify;
+pub mod window;
@@ -0,0 +1,457 @@
+//! An array of lines, used as a baseline for the ropes.
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::metric::ByteOffset;
+use crate::rope::{BuildRope, IterRope, Rope, RopeBuilder, SliceRope};
+
+/// A text buffer storing every line in its own `String`, like many simple
+/// editors do. Line-based operations are cheap, but byte offsets have to be
+/// found by walking the lines, and edits spanning several lines have to
+/// shift the whole array.
+#[derive(Debug, Clone)]
+pub struct LineArray {
+    /// The lines of the document, not including the line breaks. There's
+    /// always at least one line.
+    lines: Vec<String>,
+    len: usize,
+}
+
+/// A byte range of a [`LineArray`].
+#[derive(Debug, Clone, Copy)]
+pub struct LineArraySlice<'a> {
+    /// The lines the slice starts and ends in.
+    lines: &'a [String],
+
+    /// The byte offset at which the slice starts in the first line.
+    start: usize,
+
+    /// The byte offset at which the slice ends in the last line.
+    end: usize,
+}
+
+impl LineArray {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> LineArraySlice<'_> {
+        LineArraySlice {
+            lines: &self.lines,
+            start: 0,
+            end: self.lines.last().unwrap().len(),
+        }
+    }
+
+    pub fn insert(&mut self, at: usize, text: &str) {
+        let (line, col) = self.locate(at);
+        self.len += text.len();
+
+        let mut new_lines = text.split('\n');
+        let first = new_lines.next().unwrap();
+
+        if new_lines.clone().next().is_none() {
+            self.lines[line].insert_str(col, first);
+            return;
+        }
+
+        let tail = self.lines[line].split_off(col);
+        self.lines[line].push_str(first);
+
+        let new_lines = new_lines.map(String::from).collect::<Vec<_>>();
+        let last = line + new_lines.len();
+        self.lines.splice(line + 1..line + 1, new_lines);
+        self.lines[last].push_str(&tail);
+    }
+
+    pub fn remove(&mut self, range: Range<usize>) {
+        assert!(range.start <= range.end);
+
+        let (start_line, start_col) = self.locate(range.start);
+        let (end_line, end_col) = self.locate(range.end);
+        self.len -= range.len();
+
+        if start_line == end_line {
+            self.lines[start_line].replace_range(start_col..end_col, "");
+            return;
+        }
+
+        let tail = self.lines[end_line].split_off(end_col);
+        self.lines[start_line].truncate(start_col);
+        self.lines[start_line].push_str(&tail);
+        self.lines.drain(start_line + 1..=end_line);
+    }
+
+    #[inline]
+    pub fn byte_slice(&self, range: Range<usize>) -> LineArraySlice<'_> {
+        assert!(range.start <= range.end);
+
+        let (start_line, start) = self.locate(range.start);
+        let (end_line, end) = self.locate(range.end);
+
+        LineArraySlice {
+            lines: &self.lines[start_line..=end_line],
+            start,
+            end,
+        }
+    }
+
+    /// Returns the number of lines in the same way as [`str::lines`].
+    #[inline]
+    pub fn line_len(&self) -> usize {
+        match self.lines.last().unwrap().is_empty() {
+            true => self.lines.len() - 1,
+            false => self.lines.len(),
+        }
+    }
+
+    #[inline]
+    pub fn line_slice(&self, range: Range<usize>) -> LineArraySlice<'_> {
+        let last = self.lines.len() - 1;
+
+        // A line range ending before the last line also includes the line
+        // break of its last line, so the slice ends at the start of the next
+        // one.
+        let (end_line, end) = match range.end {
+            line if line <= last => (line, 0),
+            _ => (last, self.lines[last].len()),
+        };
+
+        let (start_line, start) = match range.start {
+            line if line <= last => (line, 0),
+            _ => (last, self.lines[last].len()),
+        };
+
+        LineArraySlice {
+            lines: &self.lines[start_line..=end_line],
+            start,
+            end,
+        }
+    }
+
+    /// Returns the line containing the given byte offset and the offset
+    /// within that line.
+    #[inline]
+    fn locate(&self, offset: usize) -> (usize, usize) {
+        assert!(offset <= self.len);
+
+        if offset == self.len {
+            let last = self.lines.len() - 1;
+            return (last, self.lines[last].len());
+        }
+
+        let mut line_start = 0;
+
+        for (idx, line) in self.lines.iter().enumerate() {
+            if offset <= line_start + line.len() {
+                return (idx, offset - line_start);
+            }
+            line_start += line.len() + 1;
+        }
+
+        unreachable!()
+    }
+}
+
+impl Default for LineArray {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            lines: vec![String::new()],
+            len: 0,
+        }
+    }
+}
+
+impl From<&str> for LineArray {
+    #[inline]
+    fn from(s: &str) -> Self {
+        Self {
+            lines: s.split('\n').map(String::from).collect(),
+            len: s.len(),
+        }
+    }
+}
+
+impl From<LineArraySlice<'_>> for LineArray {
+    #[inline]
+    fn from(s: LineArraySlice<'_>) -> Self {
+        Self::from(s.to_string().as_str())
+    }
+}
+
+impl fmt::Display for LineArray {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.as_slice(), f)
+    }
+}
+
+impl<'a> LineArraySlice<'a> {
+    #[inline]
+    pub fn chunks(&self) -> LineArrayChunks<'a> {
+        LineArrayChunks {
+            lines: self.lines,
+            start: self.start,
+            end: self.end,
+            line_break: false,
+        }
+    }
+
+    #[inline]
+    pub fn bytes(&self) -> LineArrayBytes<'a> {
+        self.chunks().flat_map(str::bytes)
+    }
+
+    #[inline]
+    pub fn chars(&self) -> LineArrayChars<'a> {
+        self.chunks().flat_map(str::chars)
+    }
+
+    #[inline]
+    pub fn lines(&self) -> LineArrayLines<'a> {
+        LineArrayLines {
+            lines: self.lines,
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+impl fmt::Display for LineArraySlice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+/// An iterator over the lines of a [`LineArraySlice`] and the line breaks
+/// between them.
+#[derive(Clone)]
+pub struct LineArrayChunks<'a> {
+    lines: &'a [String],
+    start: usize,
+    end: usize,
+    line_break: bool,
+}
+
+impl<'a> Iterator for LineArrayChunks<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.line_break {
+                self.line_break = false;
+                return Some("\n");
+            }
+
+            let (line, rest) = self.lines.split_first()?;
+            let end = if rest.is_empty() {
+                self.end
+            } else {
+                line.len()
+            };
+            let chunk = &line[self.start..end];
+
+            self.lines = rest;
+            self.start = 0;
+            self.line_break = !rest.is_empty();
+
+            if !chunk.is_empty() {
+                return Some(chunk);
+            }
+        }
+    }
+}
+
+pub type LineArrayBytes<'a> = std::iter::FlatMap<
+    LineArrayChunks<'a>,
+    std::str::Bytes<'a>,
+    fn(&'a str) -> std::str::Bytes<'a>,
+>;
+
+pub type LineArrayChars<'a> = std::iter::FlatMap<
+    LineArrayChunks<'a>,
+    std::str::Chars<'a>,
+    fn(&'a str) -> std::str::Chars<'a>,
+>;
+
+/// An iterator over the lines of a [`LineArraySlice`], not including the
+/// line breaks.
+#[derive(Clone)]
+pub struct LineArrayLines<'a> {
+    lines: &'a [String],
+    start: usize,
+    end: usize,
+}
+
+impl<'a> Iterator for LineArrayLines<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (line, rest) = self.lines.split_first()?;
+
+        let line = match rest.is_empty() {
+            // Like `str::lines`, we don't yield an empty last line.
+            true if self.start == self.end => return None,
+            true => &line[self.start..self.end],
+            false => &line[self.start..],
+        };
+
+        self.lines = rest;
+        self.start = 0;
+
+        Some(line)
+    }
+}
+
+impl Rope for LineArray {
+    const NAME: &'static str = "LineArray";
+
+    type Offset = ByteOffset;
+
+    #[inline(always)]
+    fn from_str(s: &str) -> Self {
+        s.into()
+    }
+
+    #[inline(always)]
+    fn insert(&mut self, at: usize, s: &str) {
+        self.insert(at, s);
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, range: Range<usize>) {
+        self.remove(range);
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn contents(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SliceRope for LineArray {
+    type Slice<'a> = LineArraySlice<'a>;
+
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn byte_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        self.byte_slice(range)
+    }
+
+    #[inline]
+    fn line_len(&self) -> usize {
+        self.line_len()
+    }
+
+    #[inline]
+    fn line_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        self.line_slice(range)
+    }
+
+    #[inline]
+    fn from_slice(s: Self::Slice<'_>) -> Self {
+        Self::from(s)
+    }
+}
+
+impl IterRope for LineArray {
+    type Chunks<'a> = LineArrayChunks<'a>;
+    type Bytes<'a> = LineArrayBytes<'a>;
+    type Chars<'a> = LineArrayChars<'a>;
+    type Line<'a> = &'a str;
+    type Lines<'a> = LineArrayLines<'a>;
+
+    #[inline]
+    fn chunks(&self) -> Self::Chunks<'_> {
+        self.as_slice().chunks()
+    }
+
+    #[inline]
+    fn bytes(&self) -> Self::Bytes<'_> {
+        self.as_slice().bytes()
+    }
+
+    #[inline]
+    fn chars(&self) -> Self::Chars<'_> {
+        self.as_slice().chars()
+    }
+
+    #[inline]
+    fn lines(&self) -> Self::Lines<'_> {
+        self.as_slice().lines()
+    }
+}
+
+impl BuildRope for LineArray {
+    type Builder = LineArray;
+}
+
+impl RopeBuilder for LineArray {
+    type Rope = LineArray;
+
+    #[inline]
+    fn new() -> Self {
+        LineArray::new()
+    }
+
+    #[inline]
+    fn append(mut self, s: &str) -> Self {
+        self.insert(self.len(), s);
+        self
+    }
+
+    #[inline]
+    fn build(self) -> Self::Rope {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rope::tests::{assert_edits_like_string, random_line_range, random_range};
+
+    #[test]
+    fn edits_like_string() {
+        for seed in 0..8 {
+            assert_edits_like_string::<LineArray>(seed, |array, text, rng| {
+                let range = random_range(rng, text);
+                let slice = array.byte_slice(range.clone());
+                assert_eq!(slice.to_string(), text[range.clone()]);
+                assert!(slice.chars().eq(text[range.clone()].chars()));
+                assert!(slice.lines().eq(text[range].split_terminator('\n')));
+
+                let lines = random_line_range(rng, text);
+                assert_eq!(
+                    array.line_slice(lines.clone()).to_string(),
+                    SliceRope::line_slice(text, lines)
+                );
+                assert_eq!(array.line_len(), text.lines().count());
+            });
+        }
+    }
+}
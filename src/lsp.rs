@@ -0,0 +1,365 @@
+//! Import of the `textDocument/didChange` notifications a language server
+//! receives as an editing trace.
+
+use std::fmt;
+
+use serde_json::{json, Value};
+
+use crate::metric::{CharOffset, Edit};
+use crate::recorder::Recorder;
+
+/// A position in a document as LSP addresses it: a line and an offset in
+/// UTF-16 code units into that line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub character: usize,
+}
+
+/// A change in the `contentChanges` of a `didChange` notification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// The range replaced by `text`, or `None` if it replaces the whole
+    /// document.
+    pub range: Option<(Position, Position)>,
+    pub text: String,
+}
+
+/// The changes made to one document, in the order the server received them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentLog {
+    pub uri: String,
+
+    /// The text the document was opened with, if the log has it.
+    pub start_content: Option<String>,
+
+    /// The changes of each notification.
+    pub txns: Vec<Vec<Change>>,
+}
+
+#[derive(Debug)]
+pub enum LspError {
+    /// A notification about the document doesn't have the expected shape.
+    Message { index: usize, reason: String },
+
+    /// The log doesn't open or change the document.
+    NoDocument { uri: Option<String> },
+}
+
+impl fmt::Display for LspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Message { index, reason } => write!(f, "message {index}: {reason}"),
+            Self::NoDocument { uri: Some(uri) } => write!(f, "no notification about {uri}"),
+            Self::NoDocument { uri: None } => f.write_str("no didOpen or didChange notification"),
+        }
+    }
+}
+
+impl std::error::Error for LspError {}
+
+impl DocumentLog {
+    /// Reads the notifications about the document at `uri`, or about the
+    /// first document the log mentions if `uri` is `None`.
+    ///
+    /// The log can hold the JSON-RPC messages one per line, framed by
+    /// `Content-Length` headers, or interleaved with other lines of text. The
+    /// params of a notification are also recognized on their own, like some
+    /// clients log them.
+    pub fn parse(log: &str, uri: Option<&str>) -> Result<Self, LspError> {
+        let mut document = Self {
+            uri: uri.unwrap_or_default().to_owned(),
+            start_content: None,
+            txns: Vec::new(),
+        };
+        let mut found = uri.is_some();
+
+        for (index, message) in json_values(log).into_iter().enumerate() {
+            let params = match message.get("params") {
+                Some(params) => params,
+                None => &message,
+            };
+
+            let Some(message_uri) = params.pointer("/textDocument/uri").and_then(Value::as_str)
+            else {
+                continue;
+            };
+
+            let is_change = params.get("contentChanges").is_some();
+            let is_open = !is_change && params.pointer("/textDocument/text").is_some();
+
+            if !is_change && !is_open {
+                continue;
+            }
+
+            if !found {
+                document.uri = message_uri.to_owned();
+                found = true;
+            } else if message_uri != document.uri {
+                continue;
+            }
+
+            let invalid = |reason: &str| LspError::Message {
+                index,
+                reason: reason.to_owned(),
+            };
+
+            if is_open {
+                let text = params["textDocument"]["text"]
+                    .as_str()
+                    .ok_or_else(|| invalid("textDocument.text isn't a string"))?;
+
+                // Reopening a document replaces its contents.
+                match document.start_content.is_none() && document.txns.is_empty() {
+                    true => document.start_content = Some(text.to_owned()),
+                    false => document.txns.push(vec![Change {
+                        range: None,
+                        text: text.to_owned(),
+                    }]),
+                }
+                continue;
+            }
+
+            let changes = params["contentChanges"]
+                .as_array()
+                .ok_or_else(|| invalid("contentChanges isn't an array"))?
+                .iter()
+                .map(|change| parse_change(change).ok_or_else(|| invalid("invalid content change")))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            document.txns.push(changes);
+        }
+
+        match document.start_content.is_some() || !document.txns.is_empty() {
+            true => Ok(document),
+            false => Err(LspError::NoDocument {
+                uri: uri.map(String::from),
+            }),
+        }
+    }
+
+    /// Replays the changes from `start_content`, or from the text the
+    /// document was opened with if `None`, recording them in chars.
+    /// Positions past the end of a line or of the document are clamped to
+    /// it, like language servers do.
+    pub fn record(&self, start_content: Option<&str>) -> Recorder {
+        let start = start_content
+            .or(self.start_content.as_deref())
+            .unwrap_or_default();
+
+        let mut recorder = Recorder::new(start);
+
+        for changes in &self.txns {
+            for change in changes {
+                let range = match change.range {
+                    Some((start, end)) => {
+                        char_offset(recorder.text(), start)..char_offset(recorder.text(), end)
+                    }
+                    None => 0..recorder.text().len_chars(),
+                };
+                recorder.edit(&Edit::replace(
+                    CharOffset(range.start)..CharOffset(range.end),
+                    &change.text,
+                ));
+            }
+            recorder.commit();
+        }
+
+        recorder
+    }
+
+    /// Returns the changes in their native form as newline-delimited JSON:
+    /// a first line with the `uri` and `startContent` fields, then one line
+    /// per notification holding its `contentChanges`. The document starts
+    /// like in [`Self::record`].
+    pub fn to_native(&self, start_content: Option<&str>) -> String {
+        let start = start_content
+            .or(self.start_content.as_deref())
+            .unwrap_or_default();
+
+        let header = json!({
+            "uri": self.uri,
+            "startContent": start,
+        });
+
+        let mut native = header.to_string();
+        native.push('\n');
+
+        for changes in &self.txns {
+            let changes = changes
+                .iter()
+                .map(|change| match change.range {
+                    Some((start, end)) => json!({
+                        "range": {
+                            "start": { "line": start.line, "character": start.character },
+                            "end": { "line": end.line, "character": end.character },
+                        },
+                        "text": change.text,
+                    }),
+                    None => json!({ "text": change.text }),
+                })
+                .collect::<Vec<_>>();
+
+            native.push_str(&Value::from(changes).to_string());
+            native.push('\n');
+        }
+
+        native
+    }
+}
+
+/// Returns every JSON object in `log`, skipping the text around them,
+/// including braces which don't start valid JSON.
+fn json_values(log: &str) -> Vec<Value> {
+    let mut values = Vec::new();
+    let mut offset = 0;
+
+    while let Some(start) = log[offset..].find('{') {
+        offset += start;
+
+        let mut stream = serde_json::Deserializer::from_str(&log[offset..]).into_iter::<Value>();
+        match stream.next() {
+            Some(Ok(value)) => {
+                values.push(value);
+                offset += stream.byte_offset();
+            }
+            Some(Err(_)) => offset += 1,
+            None => break,
+        }
+    }
+
+    values
+}
+
+fn parse_change(change: &Value) -> Option<Change> {
+    let text = change.get("text")?.as_str()?.to_owned();
+
+    let range = match change.get("range") {
+        None | Some(Value::Null) => None,
+        Some(range) => Some((
+            parse_position(&range["start"])?,
+            parse_position(&range["end"])?,
+        )),
+    };
+
+    Some(Change { range, text })
+}
+
+fn parse_position(position: &Value) -> Option<Position> {
+    Some(Position {
+        line: position.get("line")?.as_u64()? as usize,
+        character: position.get("character")?.as_u64()? as usize,
+    })
+}
+
+/// Converts a position into `text` to a char offset.
+fn char_offset(text: &ropey::Rope, position: Position) -> usize {
+    if position.line >= text.len_lines() {
+        return text.len_chars();
+    }
+
+    let line = text.line(position.line);
+
+    // The position can't be past the line break.
+    let mut line_len = line.len_chars();
+    while line_len > 0 && matches!(line.char(line_len - 1), '\n' | '\r') {
+        line_len -= 1;
+    }
+    let character = position.character.min(line.char_to_utf16_cu(line_len));
+
+    text.line_to_char(position.line) + line.utf16_cu_to_char(character)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn framed(messages: &[Value]) -> String {
+        messages
+            .iter()
+            .map(|message| {
+                let body = message.to_string();
+                format!("Content-Length: {}\r\n\r\n{body}", body.len())
+            })
+            .collect()
+    }
+
+    fn did_change(uri: &str, changes: Value) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didChange",
+            "params": {
+                "textDocument": { "uri": uri, "version": 2 },
+                "contentChanges": changes,
+            },
+        })
+    }
+
+    #[test]
+    fn content_length_framed() {
+        let log = framed(&[
+            json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didOpen",
+                "params": {
+                    "textDocument": { "uri": "file:///a", "version": 1, "text": "a🎉b\nc\n" },
+                },
+            }),
+            did_change(
+                "file:///b",
+                json!([{ "range": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 0, "character": 0 },
+                }, "text": "ignored" }]),
+            ),
+            // The emoji is a surrogate pair, so `b` is at character 3.
+            did_change(
+                "file:///a",
+                json!([
+                    { "range": {
+                        "start": { "line": 0, "character": 3 },
+                        "end": { "line": 0, "character": 4 },
+                    }, "text": "X" },
+                    { "range": {
+                        "start": { "line": 1, "character": 9 },
+                        "end": { "line": 1, "character": 9 },
+                    }, "text": "!" },
+                ]),
+            ),
+        ]);
+
+        let document = DocumentLog::parse(&log, None).unwrap();
+        assert_eq!(document.uri, "file:///a");
+        assert_eq!(document.start_content.as_deref(), Some("a🎉b\nc\n"));
+        assert_eq!(document.txns.len(), 1);
+
+        let recorder = document.record(None);
+        assert_eq!(recorder.text().to_string(), "a🎉X\nc!\n");
+        assert_eq!(
+            recorder.to_json()["txns"],
+            json!([{ "patches": [[2, 1, "X"], [5, 0, "!"]] }]),
+        );
+    }
+
+    #[test]
+    fn text_around_messages() {
+        let change = did_change("file:///a", json!([{ "text": "new" }]));
+        let log = format!("[Info] settings: {{tabSize: 4}}\nParams: {change}\n{{ \"a\": \n");
+
+        let document = DocumentLog::parse(&log, Some("file:///a")).unwrap();
+        assert_eq!(document.record(Some("old")).text().to_string(), "new");
+    }
+
+    #[test]
+    fn utf16_positions() {
+        let text = ropey::Rope::from_str("🎉a\r\nb");
+        let offset = |line, character| char_offset(&text, Position { line, character });
+
+        assert_eq!(offset(0, 0), 0);
+        assert_eq!(offset(0, 2), 1);
+        assert_eq!(offset(0, 3), 2);
+        assert_eq!(offset(0, 9), 2);
+        assert_eq!(offset(1, 1), 5);
+        assert_eq!(offset(2, 0), 5);
+    }
+}
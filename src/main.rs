@@ -1,51 +1,256 @@
-mod rope;
-
-use crdt_testdata::{load_testing_data, TestPatch};
+use crdt_testdata::TestPatch;
 use criterion::measurement::WallTime;
 use criterion::{
-    criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion, Throughput,
+    criterion_group, criterion_main, BatchSize, BenchmarkGroup, BenchmarkId, Criterion, Throughput,
 };
-use rope::Rope;
+use rope_benches::buffered::Buffered;
+use rope_benches::cache::CachedTrace;
+use rope_benches::gap_buffer::GapBuffer;
+use rope_benches::instrumented::{self, Instrumented};
+use rope_benches::inverse;
+use rope_benches::latency;
+use rope_benches::line_array::LineArray;
+use rope_benches::metric::{CharOffset, Metric};
+use rope_benches::piece_table::PieceTable;
+use rope_benches::rope::Rope;
+use rope_benches::scaling;
+use rope_benches::traces;
+use rope_benches::unicode::{self, Script};
+use rope_benches::utf16::{self, Utf16};
+use rope_benches::verify::{self, Reference};
+use rope_benches::window::{self, Window};
+
+/// The environment variable which, when set, replays the traces one
+/// transaction at a time through [`Rope::apply_transaction`] in the
+/// `traces_batched` group instead of patch by patch in the `traces` group.
+/// Only transactions with many patches for the size of their document are
+/// applied differently, which of the shipped traces only rustcode and
+/// sveltecomponent have a few of: imported git histories are the traces
+/// this is for.
+const BATCHED_VAR: &str = "ROPE_BENCHES_BATCHED";
+
+/// How the traces are replayed.
+#[derive(Clone, Copy)]
+struct Replay {
+    /// Whether the patches are applied a transaction at a time.
+    batched: bool,
+
+    /// Whether the ropes are created outside of the timed code, for windows
+    /// and scaled traces since they can start from large documents.
+    untimed_setup: bool,
+}
+
+/// The variants of every trace which are replayed along with it.
+struct Variants {
+    /// The scripts the trace is remapped to, see [`unicode::ENV_VAR`].
+    scripts: Vec<Script>,
 
-const TRACES: &[&str] = &[
-    "automerge-paper",
-    "rustcode",
-    "sveltecomponent",
-    "seph-blog1",
-];
+    /// Whether the trace and its remapped variants are also replayed
+    /// inverted, see [`inverse::ENV_VAR`].
+    inverted: bool,
+
+    /// The windows replayed instead of the whole traces, see
+    /// [`window::ENV_VAR`].
+    windows: Vec<Window>,
+}
 
 fn traces(c: &mut Criterion) {
-    fn bench<R: Rope>(group: &mut BenchmarkGroup<WallTime>, trace_file: &str) {
-        let mut trace = load_testing_data(&format!("./traces/{trace_file}.json.gz"));
+    fn bench<R: Rope>(
+        group: &mut BenchmarkGroup<WallTime>,
+        name: &str,
+        trace: &CachedTrace,
+        reference: &Reference,
+        replay: Replay,
+    ) {
+        let trace = trace.get::<R::Offset>();
+        let end_len = R::Offset::measure(&trace.end_content).get();
+
+        if let Err(divergence) = reference.verify::<R>(&trace, replay.batched) {
+            panic!("{} on {name}: {divergence}", R::name());
+        }
 
-        if R::EDITS_USE_BYTE_OFFSETS {
-            trace = trace.chars_to_bytes();
+        if instrumented::enabled() {
+            let mut rope = Instrumented::<R>::from_str(&trace.start_content);
+            for txn in &trace.txns {
+                for TestPatch(pos, del, ins) in &txn.patches {
+                    rope.replace(*pos..*pos + del, ins);
+                }
+            }
+            println!("{} on {}:\n{}", R::name(), name, rope.summary());
+        }
+
+        if latency::enabled() {
+            let latencies = latency::measure::<R>(&trace);
+            println!("{} on {}:\n{latencies}", R::name(), name);
         }
 
         group.throughput(Throughput::Elements(trace.len() as u64));
 
-        group.bench_function(BenchmarkId::new(trace_file, R::NAME), |b| {
-            b.iter(|| {
-                let mut rope = R::from_str(&trace.start_content);
-                for txn in &trace.txns {
-                    for TestPatch(pos, del, ins) in &txn.patches {
-                        rope.replace(*pos..*pos + del, ins);
-                    }
+        let run = |mut rope: R| {
+            for txn in &trace.txns {
+                if replay.batched {
+                    rope.apply_transaction(&txn.patches);
+                    continue;
+                }
+                for TestPatch(pos, del, ins) in &txn.patches {
+                    rope.replace(*pos..*pos + del, ins);
                 }
-                assert_eq!(rope.len(), trace.end_content.len());
-            })
+            }
+            assert_eq!(rope.len(), end_len);
+        };
+
+        group.bench_function(BenchmarkId::new(name, R::name()), |b| {
+            match replay.untimed_setup {
+                true => b.iter_batched(
+                    || R::from_str(&trace.start_content),
+                    run,
+                    BatchSize::LargeInput,
+                ),
+                false => b.iter(|| run(R::from_str(&trace.start_content))),
+            }
         });
     }
 
-    for trace in TRACES {
-        let mut group = c.benchmark_group("traces");
+    /// Replays a trace on every rope.
+    fn bench_ropes(c: &mut Criterion, name: &str, trace: &CachedTrace, replay: Replay) {
+        let reference = Reference::new(trace.get::<CharOffset>(), verify::checkpoint_interval());
+
+        if !reference.is_verified() {
+            eprintln!(
+                "{name}: documents larger than {} MB, only checking the lengths of the ropes",
+                verify::MAX_LEN >> 20
+            );
+        }
+
+        let group_name = match replay.batched {
+            true => "traces_batched",
+            false => "traces",
+        };
+        let mut group = c.benchmark_group(group_name);
+
+        bench::<String>(&mut group, name, trace, &reference, replay);
+        bench::<GapBuffer>(&mut group, name, trace, &reference, replay);
+        bench::<PieceTable>(&mut group, name, trace, &reference, replay);
+        bench::<LineArray>(&mut group, name, trace, &reference, replay);
+        bench::<crop::Rope>(&mut group, name, trace, &reference, replay);
+        bench::<jumprope::JumpRope>(&mut group, name, trace, &reference, replay);
+        bench::<jumprope::JumpRopeBuf>(&mut group, name, trace, &reference, replay);
+        bench::<ropey::Rope>(&mut group, name, trace, &reference, replay);
+        bench::<xi_rope::Rope>(&mut group, name, trace, &reference, replay);
+
+        bench::<Buffered<String>>(&mut group, name, trace, &reference, replay);
+        bench::<Buffered<GapBuffer>>(&mut group, name, trace, &reference, replay);
+        bench::<Buffered<PieceTable>>(&mut group, name, trace, &reference, replay);
+        bench::<Buffered<LineArray>>(&mut group, name, trace, &reference, replay);
+        bench::<Buffered<crop::Rope>>(&mut group, name, trace, &reference, replay);
+        bench::<Buffered<jumprope::JumpRope>>(&mut group, name, trace, &reference, replay);
+        bench::<Buffered<ropey::Rope>>(&mut group, name, trace, &reference, replay);
+        bench::<Buffered<xi_rope::Rope>>(&mut group, name, trace, &reference, replay);
+
+        if utf16::enabled() {
+            bench::<Utf16<String>>(&mut group, name, trace, &reference, replay);
+            bench::<Utf16<crop::Rope>>(&mut group, name, trace, &reference, replay);
+            bench::<Utf16<jumprope::JumpRope>>(&mut group, name, trace, &reference, replay);
+            bench::<Utf16<ropey::Rope>>(&mut group, name, trace, &reference, replay);
+            bench::<Utf16<xi_rope::Rope>>(&mut group, name, trace, &reference, replay);
+        }
+    }
 
-        bench::<String>(&mut group, trace);
-        bench::<crop::Rope>(&mut group, trace);
-        bench::<jumprope::JumpRope>(&mut group, trace);
-        bench::<jumprope::JumpRopeBuf>(&mut group, trace);
-        bench::<ropey::Rope>(&mut group, trace);
-        bench::<xi_rope::Rope>(&mut group, trace);
+    /// Replays a trace, or its windows if there are any.
+    fn bench_windows(
+        c: &mut Criterion,
+        name: &str,
+        trace: &CachedTrace,
+        replay: Replay,
+        windows: &[Window],
+    ) {
+        if windows.is_empty() {
+            return bench_ropes(c, name, trace, replay);
+        }
+
+        for window in windows {
+            if let Some(windowed) = window.apply(trace.chars()) {
+                let windowed = CachedTrace::new(windowed);
+                bench_ropes(c, &format!("{name}@{window}"), &windowed, replay);
+            }
+        }
+    }
+
+    /// Replays a trace and its variants, making each variant only when its
+    /// turn comes since they're as large as the trace.
+    fn bench_variants(
+        c: &mut Criterion,
+        name: &str,
+        trace: &CachedTrace,
+        replay: Replay,
+        variants: &Variants,
+    ) {
+        let scripts = std::iter::once(None).chain(variants.scripts.iter().map(Some));
+
+        for script in scripts {
+            let remapped;
+            let (name, trace) = match script {
+                None => (name.to_owned(), trace),
+                Some(script) => {
+                    remapped = CachedTrace::new(script.apply(trace.chars()));
+                    (format!("{name}-{script}"), &remapped)
+                }
+            };
+
+            bench_windows(c, &name, trace, replay, &variants.windows);
+
+            if variants.inverted {
+                let inverted = CachedTrace::new(inverse::invert(trace.chars()));
+                let name = format!("{name}-inverted");
+                bench_windows(c, &name, &inverted, replay, &variants.windows);
+            }
+        }
+    }
+
+    let trace_files = traces::from_env().unwrap_or_else(|err| {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    });
+
+    let traces = trace_files
+        .iter()
+        .map(|trace_file| (trace_file.name.clone(), CachedTrace::load(trace_file)))
+        .collect::<Vec<_>>();
+
+    let windows = window::from_env();
+
+    let replay = Replay {
+        batched: std::env::var_os(BATCHED_VAR).is_some(),
+        untimed_setup: !windows.is_empty(),
+    };
+
+    let variants = Variants {
+        scripts: unicode::from_env(),
+        inverted: inverse::enabled(),
+        windows,
+    };
+
+    for (name, trace) in &traces {
+        bench_variants(c, name, trace, replay, &variants);
+    }
+
+    let chars = traces
+        .iter()
+        .map(|(name, trace)| (name.as_str(), trace.chars()))
+        .collect::<Vec<_>>();
+
+    // Scaled traces start from documents large enough that creating their
+    // ropes would take most of the time.
+    let scaled_replay = Replay {
+        untimed_setup: true,
+        ..replay
+    };
+
+    for transform in scaling::from_env() {
+        for (name, scaled) in transform.apply(&chars) {
+            let scaled = CachedTrace::new(scaled);
+            bench_variants(c, &name, &scaled, scaled_replay, &variants);
+        }
     }
 }
 
@@ -0,0 +1,318 @@
+//! Typed offsets for the units ropes use to address their contents, and
+//! helpers to translate offsets and edits between them.
+
+use std::fmt::Debug;
+use std::ops::Range;
+
+use crdt_testdata::{TestData, TestPatch};
+
+use crate::rope::Rope;
+
+/// A unit in which offsets into a document can be measured.
+pub trait Metric: Copy + Debug + Eq + Ord {
+    const NAME: &'static str;
+
+    fn new(offset: usize) -> Self;
+
+    fn get(self) -> usize;
+
+    /// Returns the length of `text` in this metric.
+    fn measure(text: &str) -> Self;
+
+    /// Converts this offset into `text` to a byte offset by scanning `text`
+    /// from the start.
+    fn to_byte_in(self, text: &str) -> usize;
+
+    /// Converts a char offset into `text` to this metric.
+    fn from_char(text: &ropey::Rope, char_offset: usize) -> Self;
+
+    /// Converts this offset into `text` to a char offset.
+    fn to_char(self, text: &ropey::Rope) -> usize;
+}
+
+/// An offset measured in bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteOffset(pub usize);
+
+/// An offset measured in Unicode codepoints.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CharOffset(pub usize);
+
+/// An offset measured in UTF-16 code units.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Utf16Offset(pub usize);
+
+/// An offset measured in line breaks: the start of the line with that index.
+/// Converting an offset inside a line rounds it down to the start of the
+/// line.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineOffset(pub usize);
+
+impl Metric for ByteOffset {
+    const NAME: &'static str = "bytes";
+
+    #[inline]
+    fn new(offset: usize) -> Self {
+        Self(offset)
+    }
+
+    #[inline]
+    fn get(self) -> usize {
+        self.0
+    }
+
+    #[inline]
+    fn measure(text: &str) -> Self {
+        Self(text.len())
+    }
+
+    #[inline]
+    fn to_byte_in(self, _: &str) -> usize {
+        self.0
+    }
+
+    #[inline]
+    fn from_char(text: &ropey::Rope, char_offset: usize) -> Self {
+        Self(text.char_to_byte(char_offset))
+    }
+
+    #[inline]
+    fn to_char(self, text: &ropey::Rope) -> usize {
+        text.byte_to_char(self.0)
+    }
+}
+
+impl Metric for CharOffset {
+    const NAME: &'static str = "chars";
+
+    #[inline]
+    fn new(offset: usize) -> Self {
+        Self(offset)
+    }
+
+    #[inline]
+    fn get(self) -> usize {
+        self.0
+    }
+
+    #[inline]
+    fn measure(text: &str) -> Self {
+        Self(text.chars().count())
+    }
+
+    #[inline]
+    fn to_byte_in(self, text: &str) -> usize {
+        text.char_indices()
+            .nth(self.0)
+            .map_or(text.len(), |(idx, _)| idx)
+    }
+
+    #[inline]
+    fn from_char(_: &ropey::Rope, char_offset: usize) -> Self {
+        Self(char_offset)
+    }
+
+    #[inline]
+    fn to_char(self, _: &ropey::Rope) -> usize {
+        self.0
+    }
+}
+
+impl Metric for Utf16Offset {
+    const NAME: &'static str = "utf16";
+
+    #[inline]
+    fn new(offset: usize) -> Self {
+        Self(offset)
+    }
+
+    #[inline]
+    fn get(self) -> usize {
+        self.0
+    }
+
+    #[inline]
+    fn measure(text: &str) -> Self {
+        Self(text.chars().map(char::len_utf16).sum())
+    }
+
+    #[inline]
+    fn to_byte_in(self, text: &str) -> usize {
+        let mut utf16_offset = 0;
+        for (idx, ch) in text.char_indices() {
+            if utf16_offset >= self.0 {
+                return idx;
+            }
+            utf16_offset += ch.len_utf16();
+        }
+        text.len()
+    }
+
+    #[inline]
+    fn from_char(text: &ropey::Rope, char_offset: usize) -> Self {
+        Self(text.char_to_utf16_cu(char_offset))
+    }
+
+    #[inline]
+    fn to_char(self, text: &ropey::Rope) -> usize {
+        text.utf16_cu_to_char(self.0)
+    }
+}
+
+impl Metric for LineOffset {
+    const NAME: &'static str = "lines";
+
+    #[inline]
+    fn new(offset: usize) -> Self {
+        Self(offset)
+    }
+
+    #[inline]
+    fn get(self) -> usize {
+        self.0
+    }
+
+    /// Returns the number of line breaks, which is the offset of the start
+    /// of the last line.
+    #[inline]
+    fn measure(text: &str) -> Self {
+        Self(text.matches('\n').count())
+    }
+
+    #[inline]
+    fn to_byte_in(self, text: &str) -> usize {
+        match self.0 {
+            0 => 0,
+            line => text
+                .match_indices('\n')
+                .nth(line - 1)
+                .map_or(text.len(), |(idx, _)| idx + 1),
+        }
+    }
+
+    #[inline]
+    fn from_char(text: &ropey::Rope, char_offset: usize) -> Self {
+        Self(text.char_to_line(char_offset))
+    }
+
+    #[inline]
+    fn to_char(self, text: &ropey::Rope) -> usize {
+        text.line_to_char(self.0)
+    }
+}
+
+/// An edit replacing the text between two offsets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit<M> {
+    pub range: Range<M>,
+    pub text: String,
+}
+
+impl<M: Metric> Edit<M> {
+    #[inline]
+    pub fn insert(at: M, text: &str) -> Self {
+        Self {
+            range: at..at,
+            text: text.to_owned(),
+        }
+    }
+
+    #[inline]
+    pub fn remove(range: Range<M>) -> Self {
+        Self {
+            range,
+            text: String::new(),
+        }
+    }
+
+    #[inline]
+    pub fn replace(range: Range<M>, text: &str) -> Self {
+        Self {
+            range,
+            text: text.to_owned(),
+        }
+    }
+
+    /// Applies the edit to a rope addressed in the same metric, going
+    /// through [`Rope::insert`] or [`Rope::remove`] if the edit is a pure
+    /// insertion or deletion.
+    #[inline(always)]
+    pub fn apply<R: Rope<Offset = M>>(&self, rope: &mut R) {
+        let range = self.range.start.get()..self.range.end.get();
+
+        if range.is_empty() {
+            rope.insert(range.start, &self.text);
+        } else if self.text.is_empty() {
+            rope.remove(range);
+        } else {
+            rope.replace(range, &self.text);
+        }
+    }
+}
+
+/// A document used to translate offsets from one metric to another while
+/// it's being edited.
+pub struct Translator {
+    text: ropey::Rope,
+}
+
+impl Translator {
+    #[inline]
+    pub fn new(text: &str) -> Self {
+        Self { text: text.into() }
+    }
+
+    /// The document after every edit translated so far.
+    #[inline]
+    pub fn text(&self) -> &ropey::Rope {
+        &self.text
+    }
+
+    /// Translates an edit to the current document into the `To` metric, then
+    /// applies it.
+    pub fn translate<From: Metric, To: Metric>(&mut self, edit: &Edit<From>) -> Edit<To> {
+        let start = edit.range.start.to_char(&self.text);
+        let end = edit.range.end.to_char(&self.text);
+
+        let translated = Edit {
+            range: To::from_char(&self.text, start)..To::from_char(&self.text, end),
+            text: edit.text.clone(),
+        };
+
+        self.text.remove(start..end);
+        self.text.insert(start, &edit.text);
+
+        translated
+    }
+}
+
+/// Translates a sequence of edits applied to `start`.
+pub fn translate_edits<From: Metric, To: Metric>(
+    start: &str,
+    edits: &[Edit<From>],
+) -> Vec<Edit<To>> {
+    let mut translator = Translator::new(start);
+    edits
+        .iter()
+        .map(|edit| translator.translate(edit))
+        .collect()
+}
+
+/// Translates the patches of a trace, which are given in chars, into the `M`
+/// metric. The translated trace has its own copy of the contents of the
+/// trace.
+pub fn translate_trace<M: Metric>(trace: &TestData) -> TestData {
+    let mut translated = trace.clone();
+    let mut translator = Translator::new(&trace.start_content);
+
+    for txn in &mut translated.txns {
+        for TestPatch(pos, del, ins) in &mut txn.patches {
+            let edit = Edit::replace(CharOffset(*pos)..CharOffset(*pos + *del), ins);
+            let Edit { range, .. } = translator.translate::<_, M>(&edit);
+            *pos = range.start.get();
+            *del = range.end.get() - range.start.get();
+        }
+    }
+
+    translated
+}
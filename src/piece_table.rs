@@ -0,0 +1,573 @@
+//! A piece table, used as a baseline for the ropes.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Range;
+
+use crate::metric::ByteOffset;
+use crate::rope::{BuildRope, IterRope, Rope, RopeBuilder, SliceRope};
+
+/// A text buffer that never modifies the text it was created from. Inserted
+/// text is appended to a second buffer, and the document is described by a
+/// list of pieces pointing into either of the two.
+#[derive(Debug, Clone, Default)]
+pub struct PieceTable {
+    original: String,
+    added: String,
+    pieces: Vec<Piece>,
+    len: usize,
+
+    /// The index of a piece and the byte offset at which it starts, used as
+    /// the starting point when looking for the piece containing an offset.
+    /// Consecutive edits tend to be close to each other, so this saves us
+    /// from scanning the pieces from the start every time.
+    cursor: (usize, usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    source: Source,
+    start: usize,
+    len: usize,
+    line_breaks: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Original,
+    Added,
+}
+
+/// A byte range of a [`PieceTable`].
+#[derive(Clone, Copy)]
+pub struct PieceSlice<'a> {
+    table: &'a PieceTable,
+    pieces: &'a [Piece],
+    skip: usize,
+    len: usize,
+}
+
+impl PieceTable {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> PieceSlice<'_> {
+        PieceSlice {
+            table: self,
+            pieces: &self.pieces,
+            skip: 0,
+            len: self.len,
+        }
+    }
+
+    pub fn insert(&mut self, at: usize, text: &str) {
+        assert!(at <= self.len);
+
+        if text.is_empty() {
+            return;
+        }
+
+        let idx = self.split_at(at);
+        let start = self.added.len();
+        self.added.push_str(text);
+        self.len += text.len();
+
+        // Typing usually appends to the text we just inserted, in which case
+        // we can extend the previous piece instead of adding a new one.
+        if let Some(prev) = idx.checked_sub(1).map(|prev| &mut self.pieces[prev]) {
+            if prev.source == Source::Added && prev.start + prev.len == start {
+                prev.len += text.len();
+                prev.line_breaks += count_line_breaks(text);
+                self.cursor = (idx - 1, at - (prev.len - text.len()));
+                return;
+            }
+        }
+
+        let piece = Piece {
+            source: Source::Added,
+            start,
+            len: text.len(),
+            line_breaks: count_line_breaks(text),
+        };
+
+        self.pieces.insert(idx, piece);
+        self.cursor = (idx, at);
+    }
+
+    pub fn remove(&mut self, range: Range<usize>) {
+        assert!(range.start <= range.end && range.end <= self.len);
+
+        if range.is_empty() {
+            return;
+        }
+
+        let start = self.split_at(range.start);
+        let end = self.split_at(range.end);
+        self.pieces.drain(start..end);
+        self.len -= range.len();
+        self.cursor = (start, range.start);
+    }
+
+    #[inline]
+    pub fn byte_slice(&self, range: Range<usize>) -> PieceSlice<'_> {
+        self.as_slice().byte_slice(range)
+    }
+
+    /// Returns the number of lines in the same way as [`str::lines`].
+    #[inline]
+    pub fn line_len(&self) -> usize {
+        let line_breaks = self.pieces.iter().map(|p| p.line_breaks).sum::<usize>();
+
+        match self.pieces.last() {
+            Some(last) if !self.text(last).ends_with('\n') => line_breaks + 1,
+            _ => line_breaks,
+        }
+    }
+
+    #[inline]
+    pub fn line_slice(&self, range: Range<usize>) -> PieceSlice<'_> {
+        let start = self.byte_of_line(range.start);
+        let end = self.byte_of_line(range.end);
+        self.byte_slice(start..end)
+    }
+
+    /// Returns the byte offset of the start of the given line, or the length
+    /// of the document if it has fewer lines than that.
+    pub fn byte_of_line(&self, line: usize) -> usize {
+        if line == 0 {
+            return 0;
+        }
+
+        let mut line_breaks = 0;
+        let mut offset = 0;
+
+        for piece in &self.pieces {
+            if line_breaks + piece.line_breaks >= line {
+                let (idx, _) = self
+                    .text(piece)
+                    .match_indices('\n')
+                    .nth(line - line_breaks - 1)
+                    .unwrap();
+                return offset + idx + 1;
+            }
+            line_breaks += piece.line_breaks;
+            offset += piece.len;
+        }
+
+        self.len
+    }
+
+    #[inline]
+    fn text(&self, piece: &Piece) -> &str {
+        let source = match piece.source {
+            Source::Original => &self.original,
+            Source::Added => &self.added,
+        };
+        &source[piece.start..piece.start + piece.len]
+    }
+
+    /// Returns the index of the piece containing the given byte offset
+    /// together with the offset at which that piece starts, or the number of
+    /// pieces and the length of the document if the offset is at the end.
+    #[inline]
+    fn locate(&self, offset: usize) -> (usize, usize) {
+        let (mut idx, mut start) = self.cursor;
+
+        while idx > 0 && offset < start {
+            idx -= 1;
+            start -= self.pieces[idx].len;
+        }
+
+        while idx < self.pieces.len() && offset >= start + self.pieces[idx].len {
+            start += self.pieces[idx].len;
+            idx += 1;
+        }
+
+        (idx, start)
+    }
+
+    /// Makes sure a piece starts at the given byte offset, splitting the one
+    /// containing it if necessary, and returns its index.
+    fn split_at(&mut self, offset: usize) -> usize {
+        let (idx, start) = self.locate(offset);
+
+        if offset == start {
+            self.cursor = (idx, start);
+            return idx;
+        }
+
+        let piece = self.pieces[idx];
+        let text = self.text(&piece);
+        let split = offset - start;
+        assert!(text.is_char_boundary(split));
+
+        // Only count the line breaks on the shorter side of the split.
+        let left_breaks = if split <= piece.len / 2 {
+            count_line_breaks(&text[..split])
+        } else {
+            piece.line_breaks - count_line_breaks(&text[split..])
+        };
+
+        let left = Piece {
+            len: split,
+            line_breaks: left_breaks,
+            ..piece
+        };
+
+        let right = Piece {
+            start: piece.start + split,
+            len: piece.len - split,
+            line_breaks: piece.line_breaks - left_breaks,
+            ..piece
+        };
+
+        self.pieces[idx] = left;
+        self.pieces.insert(idx + 1, right);
+        self.cursor = (idx + 1, offset);
+        idx + 1
+    }
+}
+
+#[inline]
+fn count_line_breaks(s: &str) -> usize {
+    s.bytes().filter(|&b| b == b'\n').count()
+}
+
+impl From<&str> for PieceTable {
+    #[inline]
+    fn from(s: &str) -> Self {
+        let pieces = if s.is_empty() {
+            Vec::new()
+        } else {
+            vec![Piece {
+                source: Source::Original,
+                start: 0,
+                len: s.len(),
+                line_breaks: count_line_breaks(s),
+            }]
+        };
+
+        Self {
+            original: s.to_owned(),
+            added: String::new(),
+            pieces,
+            len: s.len(),
+            cursor: (0, 0),
+        }
+    }
+}
+
+impl From<PieceSlice<'_>> for PieceTable {
+    #[inline]
+    fn from(s: PieceSlice<'_>) -> Self {
+        Self::from(s.chunks().collect::<String>().as_str())
+    }
+}
+
+impl fmt::Display for PieceTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.as_slice(), f)
+    }
+}
+
+impl<'a> PieceSlice<'a> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn byte_slice(&self, range: Range<usize>) -> Self {
+        assert!(range.start <= range.end && range.end <= self.len);
+
+        let mut skip = self.skip + range.start;
+        let mut pieces = self.pieces;
+
+        while let Some((first, rest)) = pieces.split_first() {
+            if skip < first.len {
+                break;
+            }
+            skip -= first.len;
+            pieces = rest;
+        }
+
+        Self {
+            table: self.table,
+            pieces,
+            skip,
+            len: range.len(),
+        }
+    }
+
+    #[inline]
+    pub fn chunks(&self) -> PieceChunks<'a> {
+        PieceChunks {
+            table: self.table,
+            pieces: self.pieces.iter(),
+            skip: self.skip,
+            remaining: self.len,
+        }
+    }
+
+    #[inline]
+    pub fn bytes(&self) -> PieceBytes<'a> {
+        self.chunks().flat_map(str::bytes)
+    }
+
+    #[inline]
+    pub fn chars(&self) -> PieceChars<'a> {
+        self.chunks().flat_map(str::chars)
+    }
+
+    #[inline]
+    pub fn lines(&self) -> PieceLines<'a> {
+        PieceLines {
+            chunks: self.chunks(),
+            current: "",
+        }
+    }
+}
+
+impl fmt::Debug for PieceSlice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.chunks()).finish()
+    }
+}
+
+impl fmt::Display for PieceSlice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+/// An iterator over the text of the pieces in a [`PieceSlice`].
+#[derive(Clone)]
+pub struct PieceChunks<'a> {
+    table: &'a PieceTable,
+    pieces: std::slice::Iter<'a, Piece>,
+    skip: usize,
+    remaining: usize,
+}
+
+impl<'a> Iterator for PieceChunks<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let text = &self.table.text(self.pieces.next()?)[self.skip..];
+        let chunk = &text[..text.len().min(self.remaining)];
+        self.skip = 0;
+        self.remaining -= chunk.len();
+        Some(chunk)
+    }
+}
+
+pub type PieceBytes<'a> =
+    std::iter::FlatMap<PieceChunks<'a>, std::str::Bytes<'a>, fn(&'a str) -> std::str::Bytes<'a>>;
+
+pub type PieceChars<'a> =
+    std::iter::FlatMap<PieceChunks<'a>, std::str::Chars<'a>, fn(&'a str) -> std::str::Chars<'a>>;
+
+/// An iterator over the lines of a [`PieceSlice`], not including the line
+/// breaks. Lines spanning more than one piece have to be allocated.
+#[derive(Clone)]
+pub struct PieceLines<'a> {
+    chunks: PieceChunks<'a>,
+    current: &'a str,
+}
+
+impl<'a> Iterator for PieceLines<'a> {
+    type Item = Cow<'a, str>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line: Option<Cow<'a, str>> = None;
+
+        loop {
+            if self.current.is_empty() {
+                match self.chunks.next() {
+                    Some(chunk) => self.current = chunk,
+                    None => return line,
+                }
+            }
+
+            let (part, rest, done) = match self.current.find('\n') {
+                Some(newline) => (&self.current[..newline], &self.current[newline + 1..], true),
+                None => (self.current, "", false),
+            };
+
+            self.current = rest;
+
+            line = Some(match line {
+                Some(mut line) => {
+                    line.to_mut().push_str(part);
+                    line
+                }
+                None => Cow::Borrowed(part),
+            });
+
+            if done {
+                return line;
+            }
+        }
+    }
+}
+
+impl Rope for PieceTable {
+    const NAME: &'static str = "PieceTable";
+
+    type Offset = ByteOffset;
+
+    #[inline(always)]
+    fn from_str(s: &str) -> Self {
+        s.into()
+    }
+
+    #[inline(always)]
+    fn insert(&mut self, at: usize, s: &str) {
+        self.insert(at, s);
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, range: Range<usize>) {
+        self.remove(range);
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn contents(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SliceRope for PieceTable {
+    type Slice<'a> = PieceSlice<'a>;
+
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn byte_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        self.byte_slice(range)
+    }
+
+    #[inline]
+    fn line_len(&self) -> usize {
+        self.line_len()
+    }
+
+    #[inline]
+    fn line_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        self.line_slice(range)
+    }
+
+    #[inline]
+    fn from_slice(s: Self::Slice<'_>) -> Self {
+        Self::from(s)
+    }
+}
+
+impl IterRope for PieceTable {
+    type Chunks<'a> = PieceChunks<'a>;
+    type Bytes<'a> = PieceBytes<'a>;
+    type Chars<'a> = PieceChars<'a>;
+    type Line<'a> = Cow<'a, str>;
+    type Lines<'a> = PieceLines<'a>;
+
+    #[inline]
+    fn chunks(&self) -> Self::Chunks<'_> {
+        self.as_slice().chunks()
+    }
+
+    #[inline]
+    fn bytes(&self) -> Self::Bytes<'_> {
+        self.as_slice().bytes()
+    }
+
+    #[inline]
+    fn chars(&self) -> Self::Chars<'_> {
+        self.as_slice().chars()
+    }
+
+    #[inline]
+    fn lines(&self) -> Self::Lines<'_> {
+        self.as_slice().lines()
+    }
+}
+
+impl BuildRope for PieceTable {
+    type Builder = PieceTable;
+}
+
+impl RopeBuilder for PieceTable {
+    type Rope = PieceTable;
+
+    #[inline]
+    fn new() -> Self {
+        PieceTable::new()
+    }
+
+    #[inline]
+    fn append(mut self, s: &str) -> Self {
+        self.insert(self.len(), s);
+        self
+    }
+
+    #[inline]
+    fn build(self) -> Self::Rope {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rope::tests::{assert_edits_like_string, random_line_range, random_range};
+
+    #[test]
+    fn edits_like_string() {
+        for seed in 0..8 {
+            assert_edits_like_string::<PieceTable>(seed, |table, text, rng| {
+                let range = random_range(rng, text);
+                let slice = table.byte_slice(range.clone());
+                assert_eq!(slice.to_string(), text[range.clone()]);
+                assert!(slice.chars().eq(text[range.clone()].chars()));
+                assert!(slice.lines().eq(text[range].split_terminator('\n')));
+
+                let lines = random_line_range(rng, text);
+                assert_eq!(
+                    table.line_slice(lines.clone()).to_string(),
+                    SliceRope::line_slice(text, lines)
+                );
+                assert_eq!(table.line_len(), text.lines().count());
+            });
+        }
+    }
+}
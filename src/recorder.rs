@@ -0,0 +1,171 @@
+//! Recording of editing traces in the format read by
+//! [`crdt_testdata::load_testing_data`].
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::{json, Value};
+
+use crate::metric::{CharOffset, Edit, Metric, Translator};
+
+/// Records the edits made to a document, grouped in transactions.
+pub struct Recorder {
+    start_content: String,
+    translator: Translator,
+    txns: Vec<Vec<Edit<CharOffset>>>,
+
+    /// The edits of the transaction which hasn't been committed yet.
+    pending: Vec<Edit<CharOffset>>,
+
+    /// The number of edits recorded so far, committed or not.
+    len: usize,
+}
+
+/// An edit which can't be recorded since one of its offsets doesn't address
+/// the document it edits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidEdit {
+    /// The index of the transaction of the edit.
+    pub txn: usize,
+
+    /// The index of the edit in its transaction.
+    pub patch: usize,
+
+    pub metric: &'static str,
+    pub offset: usize,
+
+    /// The length of the document the edit was made to.
+    pub len: usize,
+}
+
+impl fmt::Display for InvalidEdit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            txn,
+            patch,
+            metric,
+            offset,
+            len,
+        } = self;
+
+        write!(
+            f,
+            "patch {patch} of transaction {txn} is at {metric} {offset}, "
+        )?;
+
+        match offset > len {
+            true => write!(f, "past the end of the document of {len} {metric}"),
+            false => write!(f, "which isn't a char boundary"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidEdit {}
+
+impl Recorder {
+    #[inline]
+    pub fn new(start_content: &str) -> Self {
+        Self {
+            start_content: start_content.to_owned(),
+            translator: Translator::new(start_content),
+            txns: Vec::new(),
+            pending: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Records an edit to the current document, addressed in the `M` metric.
+    #[inline]
+    pub fn edit<M: Metric>(&mut self, edit: &Edit<M>) {
+        let edit = self.translator.translate::<M, CharOffset>(edit);
+        self.pending.push(edit);
+        self.len += 1;
+    }
+
+    /// Records an edit like [`Self::edit`] if its offsets are in the current
+    /// document and on char boundaries, or returns why it can't be recorded.
+    pub fn try_edit<M: Metric>(&mut self, edit: &Edit<M>) -> Result<(), InvalidEdit> {
+        let text = self.translator.text();
+        let len = M::from_char(text, text.len_chars());
+
+        for offset in [edit.range.start, edit.range.end] {
+            if offset > len || M::from_char(text, offset.to_char(text)) != offset {
+                return Err(InvalidEdit {
+                    txn: self.txns.len(),
+                    patch: self.pending.len(),
+                    metric: M::NAME,
+                    offset: offset.get(),
+                    len: len.get(),
+                });
+            }
+        }
+
+        self.edit(edit);
+        Ok(())
+    }
+
+    /// Ends the current transaction. Edits recorded afterwards go in a new
+    /// one.
+    #[inline]
+    pub fn commit(&mut self) {
+        if !self.pending.is_empty() {
+            self.txns.push(std::mem::take(&mut self.pending));
+        }
+    }
+
+    /// The document after every edit recorded so far.
+    #[inline]
+    pub fn text(&self) -> &ropey::Rope {
+        self.translator.text()
+    }
+
+    /// Returns the number of edits recorded so far.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the trace as the JSON value stored in trace files. Edits which
+    /// haven't been committed yet are part of a last transaction.
+    pub fn to_json(&self) -> Value {
+        let patch = |edit: &Edit<CharOffset>| {
+            json!([
+                edit.range.start.0,
+                edit.range.end.0 - edit.range.start.0,
+                edit.text,
+            ])
+        };
+
+        let txns = self
+            .txns
+            .iter()
+            .map(Vec::as_slice)
+            .chain((!self.pending.is_empty()).then_some(self.pending.as_slice()))
+            .map(|txn| json!({ "patches": txn.iter().map(patch).collect::<Vec<_>>() }))
+            .collect::<Vec<_>>();
+
+        json!({
+            "startContent": self.start_content,
+            "endContent": self.translator.text().to_string(),
+            "txns": txns,
+        })
+    }
+
+    /// Writes the trace to a gzipped JSON file which can be loaded with
+    /// [`crdt_testdata::load_testing_data`].
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        serde_json::to_writer(&mut encoder, &self.to_json())?;
+        encoder.finish()?.flush()
+    }
+}
@@ -1,8 +1,85 @@
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::fmt;
+use std::marker::PhantomData;
 use std::ops::Range;
 
-pub trait Rope {
+use crdt_testdata::TestPatch;
+
+use crate::metric::{ByteOffset, CharOffset, Metric};
+
+/// The length of document, in the offsets of a rope, each patch of a
+/// transaction has to make up for so that the ropes that can rebuild
+/// themselves apply it in a single pass instead of patch by patch.
+pub const REBUILD_LEN_PER_PATCH: usize = 1024;
+
+/// Returns whether a transaction with `patches` patches is worth rebuilding
+/// a document of length `len` for, since rebuilding it is linear in its
+/// length.
+#[inline]
+pub fn should_rebuild(patches: usize, len: usize) -> bool {
+    patches > 1 && patches.saturating_mul(REBUILD_LEN_PER_PATCH) >= len
+}
+
+/// An operation exercised by the benchmarks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    FromStr,
+    Build,
+    Insert,
+    Remove,
+    Replace,
+    ByteSlice,
+    LineSlice,
+    FromSlice,
+    IterChunks,
+    IterBytes,
+    IterChars,
+    IterLines,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::FromStr => "from_str",
+            Self::Build => "builder",
+            Self::Insert => "insert",
+            Self::Remove => "delete",
+            Self::Replace => "replace",
+            Self::ByteSlice => "byte_slice",
+            Self::LineSlice => "line_slice",
+            Self::FromSlice => "from_slice",
+            Self::IterChunks => "iter_chunks",
+            Self::IterBytes => "iter_bytes",
+            Self::IterChars => "iter_chars",
+            Self::IterLines => "iter_lines",
+        })
+    }
+}
+
+/// The editing interface shared by every rope we benchmark.
+pub trait Rope: Sized {
     const NAME: &'static str;
-    const EDITS_USE_BYTE_OFFSETS: bool = false;
+
+    /// The metric in which the offsets passed to the editing methods and the
+    /// value returned by [`len`](Self::len) are measured.
+    type Offset: Metric;
+
+    /// The operations this rope doesn't support. Their benchmarks are
+    /// reported as not supported instead of being run.
+    const UNSUPPORTED: &'static [Operation] = &[];
+
+    /// The name the rope is reported under. Wrappers around other ropes
+    /// include the name of the rope they wrap.
+    #[inline]
+    fn name() -> Cow<'static, str> {
+        Cow::Borrowed(Self::NAME)
+    }
+
+    #[inline]
+    fn supports(op: Operation) -> bool {
+        !Self::UNSUPPORTED.contains(&op)
+    }
 
     fn from_str(s: &str) -> Self;
 
@@ -23,15 +100,189 @@ pub trait Rope {
         }
     }
 
-    /// The returned length is interpreted as either number of codepoints or
-    /// the number of bytes depending on the value of
-    /// [`EDITS_USE_BYTE_OFFSETS`](Self::EDITS_USE_BYTE_OFFSETS).
+    /// Applies the patches of a transaction one after the other. Their
+    /// offsets are measured in [`Offset`](Self::Offset)s.
+    #[inline]
+    fn apply_transaction(&mut self, patches: &[TestPatch]) {
+        for TestPatch(pos, del, ins) in patches {
+            self.replace(*pos..*pos + del, ins);
+        }
+    }
+
+    /// The returned length is measured in [`Offset`](Self::Offset)s.
     fn len(&self) -> usize;
+
+    /// Returns the text of the rope, used to check that the benchmarks edit
+    /// it correctly.
+    fn contents(&self) -> String;
+
+    #[inline(always)]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Ropes that can be sliced by byte and line ranges.
+pub trait SliceRope: Rope {
+    type Slice<'a>
+    where
+        Self: 'a;
+
+    fn byte_len(&self) -> usize;
+
+    fn byte_slice(&self, _byte_range: Range<usize>) -> Self::Slice<'_> {
+        unsupported::<Self>(Operation::ByteSlice)
+    }
+
+    fn line_len(&self) -> usize {
+        unsupported::<Self>(Operation::LineSlice)
+    }
+
+    fn line_slice(&self, _line_range: Range<usize>) -> Self::Slice<'_> {
+        unsupported::<Self>(Operation::LineSlice)
+    }
+
+    fn from_slice(_slice: Self::Slice<'_>) -> Self {
+        unsupported::<Self>(Operation::FromSlice)
+    }
+}
+
+/// Ropes that can be iterated over by chunks, bytes, chars and lines.
+pub trait IterRope: Rope {
+    type Chunks<'a>: Iterator<Item = &'a str> + Clone
+    where
+        Self: 'a;
+
+    type Bytes<'a>: Iterator<Item = u8> + Clone
+    where
+        Self: 'a;
+
+    type Chars<'a>: Iterator<Item = char> + Clone
+    where
+        Self: 'a;
+
+    type Line<'a>
+    where
+        Self: 'a;
+
+    type Lines<'a>: Iterator<Item = Self::Line<'a>> + Clone
+    where
+        Self: 'a;
+
+    fn chunks(&self) -> Self::Chunks<'_> {
+        unsupported::<Self>(Operation::IterChunks)
+    }
+
+    fn bytes(&self) -> Self::Bytes<'_> {
+        unsupported::<Self>(Operation::IterBytes)
+    }
+
+    fn chars(&self) -> Self::Chars<'_> {
+        unsupported::<Self>(Operation::IterChars)
+    }
+
+    fn lines(&self) -> Self::Lines<'_> {
+        unsupported::<Self>(Operation::IterLines)
+    }
+}
+
+/// Ropes that can be created from a [`RopeBuilder`].
+pub trait BuildRope: Rope {
+    type Builder: RopeBuilder<Rope = Self>;
+}
+
+/// Builders that create a rope by appending text to it piece by piece.
+pub trait RopeBuilder {
+    type Rope: Rope;
+
+    fn new() -> Self;
+
+    fn append(self, s: &str) -> Self;
+
+    fn build(self) -> Self::Rope;
+}
+
+/// The type adapters use in place of the associated types of the operations
+/// they don't support. It can't be constructed.
+pub struct Unsupported<T> {
+    never: Infallible,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Clone for Unsupported<T> {
+    fn clone(&self) -> Self {
+        match self.never {}
+    }
+}
+
+impl<T> Iterator for Unsupported<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match self.never {}
+    }
+}
+
+impl<R: Rope> RopeBuilder for Unsupported<R> {
+    type Rope = R;
+
+    fn new() -> Self {
+        unsupported::<R>(Operation::Build)
+    }
+
+    fn append(self, _: &str) -> Self {
+        match self.never {}
+    }
+
+    fn build(self) -> Self::Rope {
+        match self.never {}
+    }
+}
+
+#[track_caller]
+fn unsupported<R: Rope>(op: Operation) -> ! {
+    panic!("{} doesn't support {op}", R::NAME)
+}
+
+/// Returns the ranges of the document each patch replaces, as they were
+/// before the transaction, if every patch comes after the text inserted by
+/// the previous one. This lets a transaction be applied in a single pass over
+/// the document.
+fn sequential_ranges<M: Metric>(patches: &[TestPatch]) -> Option<Vec<(Range<usize>, &str)>> {
+    let mut ranges = Vec::with_capacity(patches.len());
+    let mut shift = 0isize;
+    let mut end = 0;
+
+    for TestPatch(pos, del, ins) in patches {
+        let start = pos.checked_add_signed(-shift)?;
+        if start < end {
+            return None;
+        }
+        end = start + del;
+        ranges.push((start..end, ins.as_str()));
+        shift += M::measure(ins).get() as isize - *del as isize;
+    }
+
+    Some(ranges)
+}
+
+/// Returns the byte offset of the start of the given line, or the length of
+/// the string if it has fewer lines than that.
+#[inline]
+fn byte_of_line(s: &str, line: usize) -> usize {
+    match line {
+        0 => 0,
+        _ => s
+            .match_indices('\n')
+            .nth(line - 1)
+            .map_or(s.len(), |(idx, _)| idx + 1),
+    }
 }
 
 impl Rope for String {
     const NAME: &'static str = "String";
-    const EDITS_USE_BYTE_OFFSETS: bool = true;
+
+    type Offset = ByteOffset;
 
     #[inline(always)]
     fn from_str(s: &str) -> Self {
@@ -53,15 +304,128 @@ impl Rope for String {
         self.replace_range(range, s);
     }
 
+    fn apply_transaction(&mut self, patches: &[TestPatch]) {
+        let ranges = match should_rebuild(patches.len(), self.len()) {
+            true => sequential_ranges::<ByteOffset>(patches),
+            false => None,
+        };
+
+        let Some(ranges) = ranges else {
+            for TestPatch(pos, del, ins) in patches {
+                self.replace_range(*pos..*pos + del, ins);
+            }
+            return;
+        };
+
+        let mut text = String::with_capacity(self.len());
+        let mut copied = 0;
+        for (range, ins) in ranges {
+            text.push_str(&self[copied..range.start]);
+            text.push_str(ins);
+            copied = range.end;
+        }
+        text.push_str(&self[copied..]);
+        *self = text;
+    }
+
     #[inline(always)]
     fn len(&self) -> usize {
         self.len()
     }
+
+    #[inline]
+    fn contents(&self) -> String {
+        self.clone()
+    }
+}
+
+impl SliceRope for String {
+    type Slice<'a> = &'a str;
+
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn byte_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        &self[range]
+    }
+
+    #[inline]
+    fn line_len(&self) -> usize {
+        self.lines().count()
+    }
+
+    #[inline]
+    fn line_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        let start = byte_of_line(self, range.start);
+        let end = start + byte_of_line(&self[start..], range.end - range.start);
+        &self[start..end]
+    }
+
+    #[inline]
+    fn from_slice(s: Self::Slice<'_>) -> Self {
+        s.to_owned()
+    }
+}
+
+impl IterRope for String {
+    type Chunks<'a> = std::iter::Once<&'a str>;
+    type Bytes<'a> = std::str::Bytes<'a>;
+    type Chars<'a> = std::str::Chars<'a>;
+    type Line<'a> = &'a str;
+    type Lines<'a> = std::str::Lines<'a>;
+
+    #[inline]
+    fn chunks(&self) -> Self::Chunks<'_> {
+        std::iter::once(self)
+    }
+
+    #[inline]
+    fn bytes(&self) -> Self::Bytes<'_> {
+        self.as_str().bytes()
+    }
+
+    #[inline]
+    fn chars(&self) -> Self::Chars<'_> {
+        self.as_str().chars()
+    }
+
+    #[inline]
+    fn lines(&self) -> Self::Lines<'_> {
+        self.as_str().lines()
+    }
+}
+
+impl BuildRope for String {
+    type Builder = String;
+}
+
+impl RopeBuilder for String {
+    type Rope = String;
+
+    #[inline]
+    fn new() -> Self {
+        String::new()
+    }
+
+    #[inline]
+    fn append(mut self, s: &str) -> Self {
+        self.push_str(s);
+        self
+    }
+
+    #[inline]
+    fn build(self) -> Self::Rope {
+        self
+    }
 }
 
 impl Rope for crop::Rope {
     const NAME: &'static str = "crop";
-    const EDITS_USE_BYTE_OFFSETS: bool = true;
+
+    type Offset = ByteOffset;
 
     #[inline(always)]
     fn from_str(s: &str) -> Self {
@@ -83,15 +447,142 @@ impl Rope for crop::Rope {
         self.replace(range, s);
     }
 
+    fn apply_transaction(&mut self, patches: &[TestPatch]) {
+        let ranges = match should_rebuild(patches.len(), self.byte_len()) {
+            true => sequential_ranges::<ByteOffset>(patches),
+            false => None,
+        };
+
+        let Some(ranges) = ranges else {
+            for TestPatch(pos, del, ins) in patches {
+                self.replace(*pos..*pos + del, ins);
+            }
+            return;
+        };
+
+        let mut builder = <crop::RopeBuilder as RopeBuilder>::new();
+        let mut copied = 0;
+        for (range, ins) in ranges {
+            for chunk in self.byte_slice(copied..range.start).chunks() {
+                builder = builder.append(chunk);
+            }
+            builder = builder.append(ins);
+            copied = range.end;
+        }
+        for chunk in self.byte_slice(copied..).chunks() {
+            builder = builder.append(chunk);
+        }
+        *self = builder.build();
+    }
+
     #[inline(always)]
     fn len(&self) -> usize {
         self.byte_len()
     }
+
+    #[inline]
+    fn contents(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SliceRope for crop::Rope {
+    type Slice<'a> = crop::RopeSlice<'a>;
+
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.byte_len()
+    }
+
+    #[inline]
+    fn byte_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        self.byte_slice(range)
+    }
+
+    #[inline]
+    fn line_len(&self) -> usize {
+        self.line_len()
+    }
+
+    #[inline]
+    fn line_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        self.line_slice(range)
+    }
+
+    #[inline]
+    fn from_slice(s: Self::Slice<'_>) -> Self {
+        Self::from(s)
+    }
+}
+
+impl IterRope for crop::Rope {
+    type Chunks<'a> = crop::iter::Chunks<'a>;
+    type Bytes<'a> = crop::iter::Bytes<'a>;
+    type Chars<'a> = crop::iter::Chars<'a>;
+    type Line<'a> = crop::RopeSlice<'a>;
+    type Lines<'a> = crop::iter::Lines<'a>;
+
+    #[inline]
+    fn chunks(&self) -> Self::Chunks<'_> {
+        self.chunks()
+    }
+
+    #[inline]
+    fn bytes(&self) -> Self::Bytes<'_> {
+        self.bytes()
+    }
+
+    #[inline]
+    fn chars(&self) -> Self::Chars<'_> {
+        self.chars()
+    }
+
+    #[inline]
+    fn lines(&self) -> Self::Lines<'_> {
+        self.lines()
+    }
+}
+
+impl BuildRope for crop::Rope {
+    type Builder = crop::RopeBuilder;
+}
+
+impl RopeBuilder for crop::RopeBuilder {
+    type Rope = crop::Rope;
+
+    #[inline]
+    fn new() -> Self {
+        crop::RopeBuilder::new()
+    }
+
+    #[inline]
+    fn append(mut self, s: &str) -> Self {
+        crop::RopeBuilder::append(&mut self, s);
+        self
+    }
+
+    #[inline]
+    fn build(self) -> Self::Rope {
+        self.build()
+    }
 }
 
 impl Rope for jumprope::JumpRope {
     const NAME: &'static str = "JumpRope";
 
+    type Offset = CharOffset;
+
+    const UNSUPPORTED: &'static [Operation] = &[
+        Operation::Build,
+        Operation::ByteSlice,
+        Operation::LineSlice,
+        Operation::FromSlice,
+        Operation::IterChunks,
+        Operation::IterBytes,
+        Operation::IterChars,
+        Operation::IterLines,
+    ];
+
     #[inline(always)]
     fn from_str(s: &str) -> Self {
         s.into()
@@ -116,6 +607,32 @@ impl Rope for jumprope::JumpRope {
     fn len(&self) -> usize {
         self.len_chars()
     }
+
+    #[inline]
+    fn contents(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SliceRope for jumprope::JumpRope {
+    type Slice<'a> = Unsupported<()>;
+
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.len_bytes()
+    }
+}
+
+impl IterRope for jumprope::JumpRope {
+    type Chunks<'a> = Unsupported<&'a str>;
+    type Bytes<'a> = Unsupported<u8>;
+    type Chars<'a> = Unsupported<char>;
+    type Line<'a> = ();
+    type Lines<'a> = Unsupported<()>;
+}
+
+impl BuildRope for jumprope::JumpRope {
+    type Builder = Unsupported<Self>;
 }
 
 impl Rope for jumprope::JumpRopeBuf {
@@ -123,6 +640,19 @@ impl Rope for jumprope::JumpRopeBuf {
     // benchmarks by passing `JumpR***` to the CLI.
     const NAME: &'static str = "JumpBufRope";
 
+    type Offset = CharOffset;
+
+    const UNSUPPORTED: &'static [Operation] = &[
+        Operation::Build,
+        Operation::ByteSlice,
+        Operation::LineSlice,
+        Operation::FromSlice,
+        Operation::IterChunks,
+        Operation::IterBytes,
+        Operation::IterChars,
+        Operation::IterLines,
+    ];
+
     #[inline(always)]
     fn from_str(s: &str) -> Self {
         s.into()
@@ -142,11 +672,39 @@ impl Rope for jumprope::JumpRopeBuf {
     fn len(&self) -> usize {
         self.len_chars()
     }
+
+    #[inline]
+    fn contents(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SliceRope for jumprope::JumpRopeBuf {
+    type Slice<'a> = Unsupported<()>;
+
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.len_bytes()
+    }
+}
+
+impl IterRope for jumprope::JumpRopeBuf {
+    type Chunks<'a> = Unsupported<&'a str>;
+    type Bytes<'a> = Unsupported<u8>;
+    type Chars<'a> = Unsupported<char>;
+    type Line<'a> = ();
+    type Lines<'a> = Unsupported<()>;
+}
+
+impl BuildRope for jumprope::JumpRopeBuf {
+    type Builder = Unsupported<Self>;
 }
 
 impl Rope for ropey::Rope {
     const NAME: &'static str = "Ropey";
 
+    type Offset = CharOffset;
+
     #[inline(always)]
     fn from_str(s: &str) -> Self {
         s.into()
@@ -162,15 +720,148 @@ impl Rope for ropey::Rope {
         self.remove(range);
     }
 
+    fn apply_transaction(&mut self, patches: &[TestPatch]) {
+        let ranges = match should_rebuild(patches.len(), self.len_chars()) {
+            true => sequential_ranges::<CharOffset>(patches),
+            false => None,
+        };
+
+        let Some(ranges) = ranges else {
+            for TestPatch(pos, del, ins) in patches {
+                self.replace(*pos..*pos + del, ins);
+            }
+            return;
+        };
+
+        let mut builder = <ropey::RopeBuilder as RopeBuilder>::new();
+        let mut copied = 0;
+        for (range, ins) in ranges {
+            for chunk in self.slice(copied..range.start).chunks() {
+                builder = builder.append(chunk);
+            }
+            builder = builder.append(ins);
+            copied = range.end;
+        }
+        for chunk in self.slice(copied..).chunks() {
+            builder = builder.append(chunk);
+        }
+        *self = builder.build();
+    }
+
     #[inline(always)]
     fn len(&self) -> usize {
         self.len_chars()
     }
+
+    #[inline]
+    fn contents(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SliceRope for ropey::Rope {
+    type Slice<'a> = ropey::RopeSlice<'a>;
+
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.len_bytes()
+    }
+
+    #[inline]
+    fn byte_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        self.byte_slice(range)
+    }
+
+    #[inline]
+    fn line_len(&self) -> usize {
+        // Ropey counts the empty line after a trailing line break, which
+        // `str::lines` doesn't.
+        match self.len_chars() {
+            0 => 0,
+            len if self.char(len - 1) == '\n' => self.len_lines() - 1,
+            _ => self.len_lines(),
+        }
+    }
+
+    #[inline]
+    fn line_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        let start = self.line_to_byte(range.start);
+        let end = self.line_to_byte(range.end);
+        self.byte_slice(start..end)
+    }
+
+    #[inline]
+    fn from_slice(s: Self::Slice<'_>) -> Self {
+        Self::from(s)
+    }
+}
+
+impl IterRope for ropey::Rope {
+    type Chunks<'a> = ropey::iter::Chunks<'a>;
+    type Bytes<'a> = ropey::iter::Bytes<'a>;
+    type Chars<'a> = ropey::iter::Chars<'a>;
+    type Line<'a> = ropey::RopeSlice<'a>;
+    type Lines<'a> = std::iter::Take<ropey::iter::Lines<'a>>;
+
+    #[inline]
+    fn chunks(&self) -> Self::Chunks<'_> {
+        self.chunks()
+    }
+
+    #[inline]
+    fn bytes(&self) -> Self::Bytes<'_> {
+        self.bytes()
+    }
+
+    #[inline]
+    fn chars(&self) -> Self::Chars<'_> {
+        self.chars()
+    }
+
+    #[inline]
+    fn lines(&self) -> Self::Lines<'_> {
+        self.lines().take(SliceRope::line_len(self))
+    }
+}
+
+impl BuildRope for ropey::Rope {
+    type Builder = ropey::RopeBuilder;
+}
+
+impl RopeBuilder for ropey::RopeBuilder {
+    type Rope = ropey::Rope;
+
+    #[inline]
+    fn new() -> Self {
+        ropey::RopeBuilder::new()
+    }
+
+    #[inline]
+    fn append(mut self, s: &str) -> Self {
+        ropey::RopeBuilder::append(&mut self, s);
+        self
+    }
+
+    #[inline]
+    fn build(self) -> Self::Rope {
+        self.finish()
+    }
 }
 
 impl Rope for xi_rope::Rope {
     const NAME: &'static str = "xi_rope";
-    const EDITS_USE_BYTE_OFFSETS: bool = true;
+
+    type Offset = ByteOffset;
+
+    const UNSUPPORTED: &'static [Operation] = &[
+        // Slicing an xi_rope already returns an owned rope, so there's
+        // nothing to convert.
+        Operation::FromSlice,
+        // xi_rope only iterates over chunks and lines, and iterating over
+        // the bytes or chars of the chunks would measure the adapter.
+        Operation::IterBytes,
+        Operation::IterChars,
+    ];
 
     #[inline(always)]
     fn from_str(s: &str) -> Self {
@@ -196,4 +887,401 @@ impl Rope for xi_rope::Rope {
     fn len(&self) -> usize {
         self.len()
     }
+
+    #[inline]
+    fn contents(&self) -> String {
+        String::from(self)
+    }
+}
+
+impl SliceRope for xi_rope::Rope {
+    type Slice<'a> = xi_rope::Rope;
+
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn byte_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        self.slice(range)
+    }
+
+    #[inline]
+    fn line_len(&self) -> usize {
+        // The last line is only counted by `line_of_offset` if it ends with a
+        // line break, while `str::lines` counts it if it isn't empty.
+        let line_breaks = self.line_of_offset(self.len());
+        match self.len() {
+            0 => 0,
+            len if self.byte_at(len - 1) == b'\n' => line_breaks,
+            _ => line_breaks + 1,
+        }
+    }
+
+    #[inline]
+    fn line_slice(&self, range: Range<usize>) -> Self::Slice<'_> {
+        let start = self.offset_of_line(range.start);
+        let end = self.offset_of_line(range.end);
+        self.slice(start..end)
+    }
+}
+
+/// An iterator over the lines of a [`xi_rope::Rope`], like
+/// [`xi_rope::rope::Lines`] but implementing `Clone`. It keeps track of the
+/// offset it's at so that clones resume from there.
+pub struct XiRopeLines<'a> {
+    rope: &'a xi_rope::Rope,
+    lines: xi_rope::rope::LinesRaw<'a>,
+    offset: usize,
+}
+
+impl Clone for XiRopeLines<'_> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            rope: self.rope,
+            lines: self.rope.lines_raw(self.offset..),
+            offset: self.offset,
+        }
+    }
+}
+
+impl<'a> Iterator for XiRopeLines<'a> {
+    type Item = Cow<'a, str>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = self.lines.next()?;
+        self.offset += line.len();
+
+        // Strips the line break the same way `xi_rope::rope::Lines` does.
+        let content_len = match line.strip_suffix('\n') {
+            Some(content) => content.strip_suffix('\r').unwrap_or(content).len(),
+            None => line.len(),
+        };
+
+        match &mut line {
+            Cow::Borrowed(line) => *line = &line[..content_len],
+            Cow::Owned(line) => line.truncate(content_len),
+        }
+
+        Some(line)
+    }
+}
+
+/// An iterator over the chunks of a [`xi_rope::Rope`], like
+/// [`xi_rope::rope::ChunkIter`] but implementing `Clone`. It keeps track of
+/// the offset it's at so that clones resume from there.
+pub struct XiRopeChunks<'a> {
+    rope: &'a xi_rope::Rope,
+    chunks: xi_rope::rope::ChunkIter<'a>,
+    offset: usize,
+}
+
+impl Clone for XiRopeChunks<'_> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            rope: self.rope,
+            chunks: self.rope.iter_chunks(self.offset..),
+            offset: self.offset,
+        }
+    }
+}
+
+impl<'a> Iterator for XiRopeChunks<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunks.next()?;
+        self.offset += chunk.len();
+        Some(chunk)
+    }
+}
+
+impl IterRope for xi_rope::Rope {
+    type Chunks<'a> = XiRopeChunks<'a>;
+    type Bytes<'a> = Unsupported<u8>;
+    type Chars<'a> = Unsupported<char>;
+    type Line<'a> = Cow<'a, str>;
+    type Lines<'a> = XiRopeLines<'a>;
+
+    #[inline]
+    fn chunks(&self) -> Self::Chunks<'_> {
+        XiRopeChunks {
+            rope: self,
+            chunks: self.iter_chunks(..),
+            offset: 0,
+        }
+    }
+
+    #[inline]
+    fn lines(&self) -> Self::Lines<'_> {
+        XiRopeLines {
+            rope: self,
+            lines: self.lines_raw(..),
+            offset: 0,
+        }
+    }
+}
+
+pub type XiRopeBuilder = xi_rope::tree::TreeBuilder<xi_rope::RopeInfo>;
+
+impl BuildRope for xi_rope::Rope {
+    type Builder = XiRopeBuilder;
+}
+
+impl RopeBuilder for XiRopeBuilder {
+    type Rope = xi_rope::Rope;
+
+    #[inline]
+    fn new() -> Self {
+        XiRopeBuilder::new()
+    }
+
+    #[inline]
+    fn append(mut self, s: &str) -> Self {
+        self.push_str(s);
+        self
+    }
+
+    #[inline]
+    fn build(self) -> Self::Rope {
+        self.build()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::gap_buffer::GapBuffer;
+    use crate::line_array::LineArray;
+    use crate::piece_table::PieceTable;
+    use crate::synthetic::Rng;
+
+    /// The pieces of text random edits are made of, with chars of every
+    /// UTF-8 and UTF-16 length.
+    const PIECES: [&str; 8] = ["a", "bc", "é", "日本", "🎉", "\n", "d\n", "\r\n"];
+
+    /// Makes random inserts, removes and replaces to an `R` and to a
+    /// `String`, asserting that they stay the same and calling `check` with
+    /// both after every edit.
+    #[track_caller]
+    pub(crate) fn assert_edits_like_string<R: Rope>(
+        seed: u64,
+        mut check: impl FnMut(&R, &String, &mut Rng),
+    ) {
+        let mut rng = Rng(seed);
+        let mut text = random_text(&mut rng, 16);
+        let mut rope = R::from_str(&text);
+
+        for idx in 0..400 {
+            let start = random_range(&mut rng, &text).start;
+            let len = rng.below(8);
+            let end = text[start..]
+                .char_indices()
+                .nth(len)
+                .map_or(text.len(), |(end, _)| start + end);
+            let ins = random_text(&mut rng, 3);
+
+            let offset = |byte: usize| R::Offset::measure(&text[..byte]).get();
+            let (start_offset, end_offset) = (offset(start), offset(end));
+
+            match rng.below(3) {
+                0 => {
+                    rope.insert(start_offset, &ins);
+                    text.insert_str(start, &ins);
+                }
+                1 => {
+                    rope.remove(start_offset..end_offset);
+                    text.replace_range(start..end, "");
+                }
+                _ => {
+                    rope.replace(start_offset..end_offset, &ins);
+                    text.replace_range(start..end, &ins);
+                }
+            }
+
+            let name = R::name();
+            assert_eq!(
+                rope.len(),
+                R::Offset::measure(&text).get(),
+                "{name} len after edit {idx}"
+            );
+
+            // Not after every edit, so that `Buffered` gets to merge some.
+            if idx % 8 == 0 {
+                assert_eq!(rope.contents(), text, "{name} contents after edit {idx}");
+            }
+
+            check(&rope, &text, &mut rng);
+        }
+
+        assert_eq!(rope.contents(), text);
+    }
+
+    /// Returns up to `pieces` random pieces of text.
+    pub(crate) fn random_text(rng: &mut Rng, pieces: usize) -> String {
+        (0..rng.below(pieces + 1))
+            .map(|_| PIECES[rng.below(PIECES.len())])
+            .collect()
+    }
+
+    /// Returns a random byte range of `text` starting and ending on char
+    /// boundaries.
+    pub(crate) fn random_range(rng: &mut Rng, text: &str) -> Range<usize> {
+        let mut random_boundary = |text: &str| {
+            let boundaries = text.char_indices().count() + 1;
+            text.char_indices()
+                .map(|(idx, _)| idx)
+                .chain([text.len()])
+                .nth(rng.below(boundaries))
+                .unwrap()
+        };
+
+        let start = random_boundary(text);
+        start..start + random_boundary(&text[start..])
+    }
+
+    /// Returns a random range of the lines of `text`, the way
+    /// [`SliceRope::line_slice`] takes them.
+    pub(crate) fn random_line_range(rng: &mut Rng, text: &str) -> Range<usize> {
+        let lines = text.lines().count();
+        let start = rng.below(lines + 1);
+        start..start + rng.below(lines - start + 1)
+    }
+
+    #[track_caller]
+    fn assert_lines_like_str<R: SliceRope + IterRope>() {
+        for text in ["", "a", "a\n", "a\nb", "a\nb\n", "\n", "\n\n", "a\r\nb\r\n"] {
+            let rope = R::from_str(text);
+            let lines = text.lines().count();
+            assert_eq!(rope.line_len(), lines, "{} line_len of {text:?}", R::name());
+            assert_eq!(
+                rope.lines().count(),
+                lines,
+                "{} lines of {text:?}",
+                R::name()
+            );
+        }
+    }
+
+    #[test]
+    fn lines_like_str() {
+        assert_lines_like_str::<String>();
+        assert_lines_like_str::<GapBuffer>();
+        assert_lines_like_str::<PieceTable>();
+        assert_lines_like_str::<LineArray>();
+        assert_lines_like_str::<crop::Rope>();
+        assert_lines_like_str::<ropey::Rope>();
+        assert_lines_like_str::<xi_rope::Rope>();
+    }
+
+    /// Applies random transactions of a few patches to an `R` and to a
+    /// `String`, most of them with every patch after the previous one so
+    /// that the ropes which can rebuild themselves do.
+    #[track_caller]
+    fn assert_transactions_like_string<R: Rope>(seed: u64) {
+        let mut rng = Rng(seed);
+        let mut text = random_text(&mut rng, 32);
+        let mut rope = R::from_str(&text);
+
+        for idx in 0..200 {
+            let mut patches = Vec::new();
+
+            // The end of the text inserted by the previous patch.
+            let mut after = 0;
+
+            for _ in 0..1 + rng.below(4) {
+                let start = match rng.chance(0.8) {
+                    true => after + random_range(&mut rng, &text[after..]).start,
+                    false => random_range(&mut rng, &text).start,
+                };
+                let len = rng.below(4);
+                let end = text[start..]
+                    .char_indices()
+                    .nth(len)
+                    .map_or(text.len(), |(end, _)| start + end);
+                let ins = random_text(&mut rng, 2);
+
+                let offset = |byte: usize| R::Offset::measure(&text[..byte]).get();
+                let pos = offset(start);
+                patches.push(TestPatch(pos, offset(end) - pos, ins.clone()));
+
+                text.replace_range(start..end, &ins);
+                after = start + ins.len();
+            }
+
+            rope.apply_transaction(&patches);
+            assert_eq!(
+                rope.contents(),
+                text,
+                "{} after transaction {idx}",
+                R::name()
+            );
+        }
+    }
+
+    #[test]
+    fn transactions_like_string() {
+        for seed in 0..8 {
+            assert_transactions_like_string::<String>(seed);
+            assert_transactions_like_string::<crop::Rope>(seed);
+            assert_transactions_like_string::<ropey::Rope>(seed);
+        }
+    }
+
+    #[test]
+    fn rebuild_threshold() {
+        assert!(!should_rebuild(1, 0));
+        assert!(should_rebuild(2, 2 * REBUILD_LEN_PER_PATCH));
+        assert!(!should_rebuild(2, 2 * REBUILD_LEN_PER_PATCH + 1));
+        assert!(should_rebuild(usize::MAX, usize::MAX));
+    }
+
+    #[test]
+    fn sequential_patches() {
+        let patch = |pos, del, ins: &str| TestPatch(pos, del, ins.into());
+
+        // "abcdef" -> "axycdef" -> "axycdzef"
+        let patches = [patch(1, 1, "xy"), patch(5, 0, "z")];
+        assert_eq!(
+            sequential_ranges::<ByteOffset>(&patches),
+            Some(vec![(1..2, "xy"), (4..4, "z")])
+        );
+
+        // Inserting before the text the previous patch inserted.
+        let patches = [patch(1, 2, "x"), patch(1, 0, "y")];
+        assert_eq!(sequential_ranges::<ByteOffset>(&patches), None);
+
+        // Removing text the previous patch inserted.
+        let patches = [patch(1, 0, "xy"), patch(2, 2, "")];
+        assert_eq!(sequential_ranges::<ByteOffset>(&patches), None);
+
+        // The shift is measured in the metric of the patches.
+        let patches = [patch(0, 0, "é"), patch(1, 1, "")];
+        assert_eq!(
+            sequential_ranges::<CharOffset>(&patches),
+            Some(vec![(0..0, "é"), (0..1, "")])
+        );
+        assert_eq!(sequential_ranges::<ByteOffset>(&patches), None);
+    }
+
+    #[test]
+    fn xi_rope_clones_resume() {
+        let text = "a\nbc\r\n\nd";
+        let rope = xi_rope::Rope::from(text);
+
+        let mut lines = IterRope::lines(&rope);
+        assert_eq!(lines.next().as_deref(), Some("a"));
+        assert!(lines.clone().eq(text.lines().skip(1)));
+        assert!(lines.eq(text.lines().skip(1)));
+
+        let mut chunks = IterRope::chunks(&rope);
+        let first = chunks.next().unwrap();
+        assert_eq!(chunks.clone().collect::<String>(), text[first.len()..]);
+        assert_eq!(chunks.collect::<String>(), text[first.len()..]);
+    }
 }
@@ -0,0 +1,205 @@
+//! Transforms scaling traces up to the sizes of the largest documents edited
+//! in practice, while keeping their editing patterns.
+
+use std::fmt;
+
+use crdt_testdata::{TestData, TestPatch, TestTxn};
+
+/// The environment variable listing the transforms applied to the traces,
+/// separated by commas. The scaled traces are replayed after the original
+/// ones. It accepts:
+///
+/// - `pad:<size>`, which adds `<size>` of text around every trace;
+/// - `repeat:<n>`, which replays every trace on `n` copies of its document;
+/// - `concat`, which replays all the traces on one document.
+///
+/// Sizes are in bytes, with an optional `KB`, `MB` or `GB` suffix.
+///
+/// The scaled traces are made one at a time, and only their offsets in chars
+/// are kept, each rope converting them to its metric in turn. Creating the
+/// ropes from their start documents isn't timed, and their contents aren't
+/// compared to the reference above [`verify::MAX_LEN`](crate::verify::MAX_LEN)
+/// bytes, only their lengths are. A trace padded with `pad:1GB` still takes
+/// about 5 GB while it's replayed: its start and end documents in chars,
+/// their copies in the metric of the rope, and the rope itself.
+pub const ENV_VAR: &str = "ROPE_BENCHES_SCALE";
+
+/// The text used to pad traces whose documents are empty.
+const FILLER: &str = "The quick brown fox jumps over the lazy dog.\n";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transform {
+    /// Pads traces with about this many bytes, half before and half after
+    /// them.
+    Pad { bytes: usize, label: String },
+
+    /// Repeats traces this many times.
+    Repeat(usize),
+
+    /// Concatenates all the traces.
+    Concat,
+}
+
+impl Transform {
+    /// Returns the scaled traces, named after the traces they come from.
+    /// Each of them is only made when the iterator gets to it, so that a
+    /// single one of them is in memory at a time.
+    pub fn apply<'a>(
+        &'a self,
+        traces: &'a [(&'a str, &'a TestData)],
+    ) -> Box<dyn Iterator<Item = (String, TestData)> + 'a> {
+        match self {
+            Self::Pad { bytes, label } => Box::new(traces.iter().map(move |(name, trace)| {
+                let before = filler(trace, bytes / 2);
+                let after = filler(trace, bytes - bytes / 2);
+                (format!("{name}-pad-{label}"), pad(trace, &before, &after))
+            })),
+
+            Self::Repeat(times) => Box::new(traces.iter().map(move |(name, trace)| {
+                let copies = std::iter::repeat_n(*trace, *times).collect::<Vec<_>>();
+                (format!("{name}-x{times}"), concat(&copies))
+            })),
+
+            Self::Concat if traces.is_empty() => Box::new(std::iter::empty()),
+
+            Self::Concat => Box::new(std::iter::once_with(|| {
+                let traces = traces.iter().map(|(_, trace)| *trace).collect::<Vec<_>>();
+                ("concat".to_owned(), concat(&traces))
+            })),
+        }
+    }
+}
+
+impl std::str::FromStr for Transform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("pad", size)) => Ok(Self::Pad {
+                bytes: parse_size(size)?,
+                label: size.to_owned(),
+            }),
+            Some(("repeat", times)) => match times.parse() {
+                Ok(0) | Err(_) => Err(format!("invalid repeat count {times:?}")),
+                Ok(times) => Ok(Self::Repeat(times)),
+            },
+            None if s == "concat" => Ok(Self::Concat),
+            _ => Err(format!("unknown transform {s:?}")),
+        }
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pad { label, .. } => write!(f, "pad:{label}"),
+            Self::Repeat(times) => write!(f, "repeat:{times}"),
+            Self::Concat => write!(f, "concat"),
+        }
+    }
+}
+
+/// Returns the transforms listed in [`ENV_VAR`].
+pub fn from_env() -> Vec<Transform> {
+    let Ok(transforms) = std::env::var(ENV_VAR) else {
+        return Vec::new();
+    };
+
+    transforms
+        .split(',')
+        .map(str::trim)
+        .filter(|transform| !transform.is_empty())
+        .map(|transform| {
+            transform
+                .parse()
+                .unwrap_or_else(|err| panic!("invalid {ENV_VAR}: {err}"))
+        })
+        .collect()
+}
+
+/// Returns the trace with `before` prepended and `after` appended to its
+/// document.
+pub fn pad(trace: &TestData, before: &str, after: &str) -> TestData {
+    let shift = before.chars().count();
+    let mut padded = shift_trace(trace, shift);
+    padded.start_content = format!("{before}{}{after}", trace.start_content);
+    padded.end_content = format!("{before}{}{after}", trace.end_content);
+    padded
+}
+
+/// Returns a trace whose document is the concatenation of the documents of
+/// `traces`, replaying each of them in turn in its own region.
+///
+/// # Panics
+///
+/// Panics if `traces` is empty.
+pub fn concat(traces: &[&TestData]) -> TestData {
+    let (first, rest) = traces.split_first().expect("no traces to concatenate");
+    let mut concatenated = (*first).clone();
+
+    // The traces before the one being replayed are already at their end
+    // contents, and the ones after it don't move its offsets.
+    let mut shift = first.end_content.chars().count();
+
+    for trace in rest {
+        concatenated.start_content.push_str(&trace.start_content);
+        concatenated.end_content.push_str(&trace.end_content);
+        concatenated.txns.extend(shift_trace(trace, shift).txns);
+        shift += trace.end_content.chars().count();
+    }
+
+    concatenated
+}
+
+/// Returns the trace with the positions of its patches moved `shift` chars
+/// to the right.
+fn shift_trace(trace: &TestData, shift: usize) -> TestData {
+    let mut shifted = trace.clone();
+    shifted.txns = trace
+        .txns
+        .iter()
+        .map(|txn| TestTxn {
+            patches: txn
+                .patches
+                .iter()
+                .map(|TestPatch(pos, del, ins)| TestPatch(pos + shift, *del, ins.clone()))
+                .collect(),
+        })
+        .collect();
+    shifted
+}
+
+/// Returns about `bytes` bytes of text looking like the trace's document.
+fn filler(trace: &TestData, bytes: usize) -> String {
+    let sample = [&trace.end_content, &trace.start_content]
+        .into_iter()
+        .find(|text| !text.is_empty())
+        .map_or(FILLER, String::as_str);
+
+    let mut text = sample.repeat(bytes / sample.len() + 1);
+    let mut len = bytes;
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    text.truncate(len);
+    text
+}
+
+/// Parses a size in bytes with an optional `KB`, `MB` or `GB` suffix.
+pub fn parse_size(size: &str) -> Result<usize, String> {
+    let digits = size.trim_end_matches(|ch: char| ch.is_ascii_alphabetic());
+
+    let unit = match size[digits.len()..].to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" => 1 << 10,
+        "MB" => 1 << 20,
+        "GB" => 1 << 30,
+        _ => return Err(format!("invalid size {size:?}")),
+    };
+
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or_else(|| format!("invalid size {size:?}"))
+}
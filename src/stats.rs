@@ -0,0 +1,199 @@
+//! Statistics describing the edits of a trace, to explain why the ropes
+//! perform differently on different traces.
+
+use std::fmt;
+
+use crdt_testdata::{TestData, TestPatch};
+
+use crate::latency::PatchKind;
+
+/// Counts of values in power of two buckets: `0`, `1`, `2..=3`, `4..=7`...
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Histogram {
+    buckets: Vec<usize>,
+}
+
+impl Histogram {
+    #[inline]
+    pub fn add(&mut self, value: usize) {
+        let bucket = (usize::BITS - value.leading_zeros()) as usize;
+        if bucket >= self.buckets.len() {
+            self.buckets.resize(bucket + 1, 0);
+        }
+        self.buckets[bucket] += 1;
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.buckets.iter().sum()
+    }
+
+    /// Returns the smallest and largest value of each non-empty bucket with
+    /// the number of values in it.
+    pub fn buckets(&self) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(bucket, count)| match bucket {
+                0 => (0, 0, *count),
+                _ => (1 << (bucket - 1), (1 << bucket) - 1, *count),
+            })
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.count();
+
+        for (min, max, count) in self.buckets() {
+            let bucket = match min == max {
+                true => min.to_string(),
+                false => format!("{min}..={max}"),
+            };
+            writeln!(
+                f,
+                "    {bucket:>15}: {count} ({:.1}%)",
+                percent(count, total)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The size of a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocStats {
+    pub bytes: usize,
+    pub chars: usize,
+    pub lines: usize,
+    pub non_ascii_chars: usize,
+}
+
+impl DocStats {
+    pub fn new(text: &str) -> Self {
+        Self {
+            bytes: text.len(),
+            chars: text.chars().count(),
+            lines: text.lines().count(),
+            non_ascii_chars: text.chars().filter(|ch| !ch.is_ascii()).count(),
+        }
+    }
+}
+
+impl fmt::Display for DocStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} chars, {} bytes, {} lines, {:.2}% non-ASCII chars",
+            self.chars,
+            self.bytes,
+            self.lines,
+            percent(self.non_ascii_chars, self.chars),
+        )
+    }
+}
+
+/// The characteristics of a trace, with its positions and lengths in chars.
+#[derive(Debug, Clone)]
+pub struct TraceStats {
+    pub txns: usize,
+    pub patches: usize,
+    pub inserts: usize,
+    pub deletes: usize,
+    pub replaces: usize,
+
+    /// The number of chars inserted by each patch inserting text.
+    pub inserted: Histogram,
+
+    /// The number of chars deleted by each patch deleting text.
+    pub deleted: Histogram,
+
+    /// The distance between where a patch starts and where the previous one
+    /// left the cursor.
+    pub distance: Histogram,
+
+    pub start: DocStats,
+    pub end: DocStats,
+}
+
+impl TraceStats {
+    pub fn new(trace: &TestData) -> Self {
+        let mut stats = Self {
+            txns: trace.txns.len(),
+            patches: 0,
+            inserts: 0,
+            deletes: 0,
+            replaces: 0,
+            inserted: Histogram::default(),
+            deleted: Histogram::default(),
+            distance: Histogram::default(),
+            start: DocStats::new(&trace.start_content),
+            end: DocStats::new(&trace.end_content),
+        };
+
+        let mut cursor = None;
+
+        for txn in &trace.txns {
+            for patch in &txn.patches {
+                let TestPatch(pos, del, ins) = patch;
+                let ins_len = ins.chars().count();
+
+                match PatchKind::of(patch) {
+                    PatchKind::Insert => stats.inserts += 1,
+                    PatchKind::Delete => stats.deletes += 1,
+                    PatchKind::Replace => stats.replaces += 1,
+                }
+
+                if ins_len > 0 {
+                    stats.inserted.add(ins_len);
+                }
+                if *del > 0 {
+                    stats.deleted.add(*del);
+                }
+                if let Some(cursor) = cursor {
+                    stats.distance.add(pos.abs_diff(cursor));
+                }
+
+                stats.patches += 1;
+                cursor = Some(pos + ins_len);
+            }
+        }
+
+        stats
+    }
+}
+
+impl fmt::Display for TraceStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  transactions: {}", self.txns)?;
+        writeln!(f, "  patches: {}", self.patches)?;
+        writeln!(
+            f,
+            "  inserts: {} ({:.1}%), deletes: {} ({:.1}%), replaces: {} ({:.1}%)",
+            self.inserts,
+            percent(self.inserts, self.patches),
+            self.deletes,
+            percent(self.deletes, self.patches),
+            self.replaces,
+            percent(self.replaces, self.patches),
+        )?;
+        writeln!(f, "  start: {}", self.start)?;
+        writeln!(f, "  end: {}", self.end)?;
+        writeln!(f, "  chars inserted per patch:\n{}", self.inserted)?;
+        writeln!(f, "  chars deleted per patch:\n{}", self.deleted)?;
+        write!(
+            f,
+            "  distance from the previous patch in chars:\n{}",
+            self.distance
+        )
+    }
+}
+
+#[inline]
+fn percent(n: usize, total: usize) -> f64 {
+    match total {
+        0 => 0.0,
+        _ => n as f64 / total as f64 * 100.0,
+    }
+}
@@ -0,0 +1,218 @@
+//! Generation of synthetic editing traces from a simple model of typing.
+
+use crdt_testdata::{TestData, TestPatch};
+
+use crate::metric::{CharOffset, Edit};
+use crate::recorder::Recorder;
+
+/// The maximum number of patches generated per char of the target size, so
+/// that models deleting more than they insert still terminate.
+const MAX_PATCHES_PER_CHAR: usize = 20;
+
+/// The parameters of a synthetic trace. Edits come in bursts of typing or
+/// deleting at the same place, like a person editing a document would make.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypingModel {
+    /// The probability that a burst starts where the previous one ended
+    /// instead of at a random position.
+    pub locality: f64,
+
+    /// The mean number of patches in a burst.
+    pub burst_len: f64,
+
+    /// The fraction of bursts which delete text instead of inserting it.
+    pub delete_ratio: f64,
+
+    /// The probability that an insertion is pasted instead of typed.
+    pub paste_ratio: f64,
+
+    /// The mean length of a paste in chars.
+    pub paste_len: f64,
+
+    /// The probability that an inserted char is a line break.
+    pub newline_ratio: f64,
+
+    /// The length of the document the trace stops at, in chars.
+    pub target_len: usize,
+}
+
+impl Default for TypingModel {
+    fn default() -> Self {
+        Self {
+            locality: 0.9,
+            burst_len: 8.0,
+            delete_ratio: 0.25,
+            paste_ratio: 0.01,
+            paste_len: 200.0,
+            newline_ratio: 0.03,
+            target_len: 50_000,
+        }
+    }
+}
+
+impl TypingModel {
+    /// Estimates the parameters of the model from an existing trace.
+    pub fn fit(trace: &TestData) -> Self {
+        let mut patches = 0;
+        let mut bursts = 0;
+        let mut local_bursts = 0;
+        let mut delete_bursts = 0;
+        let mut typed = 0;
+        let mut newlines = 0;
+        let mut pastes = 0;
+        let mut pasted = 0;
+
+        let mut cursor = None;
+        let mut deleting = false;
+
+        for txn in &trace.txns {
+            for TestPatch(pos, del, ins) in &txn.patches {
+                let ins_len = ins.chars().count();
+                let is_delete = ins_len == 0;
+
+                // Backspacing ends where the cursor was, deleting forward
+                // starts there.
+                let is_local = cursor == Some(*pos) || (is_delete && cursor == Some(pos + del));
+
+                if !is_local || is_delete != deleting {
+                    bursts += 1;
+                    local_bursts += is_local as usize;
+                    delete_bursts += is_delete as usize;
+                }
+
+                match ins_len {
+                    0 => {}
+                    1 => {
+                        typed += 1;
+                        newlines += (ins == "\n") as usize;
+                    }
+                    _ => {
+                        pastes += 1;
+                        pasted += ins_len;
+                    }
+                }
+
+                patches += 1;
+                cursor = Some(pos + ins_len);
+                deleting = is_delete;
+            }
+        }
+
+        let default = Self::default();
+        let ratio = |n: usize, total: usize, default: f64| match total {
+            0 => default,
+            _ => n as f64 / total as f64,
+        };
+
+        Self {
+            locality: ratio(local_bursts, bursts, default.locality),
+            burst_len: ratio(patches, bursts, default.burst_len),
+            delete_ratio: ratio(delete_bursts, bursts, default.delete_ratio),
+            paste_ratio: ratio(pastes, pastes + typed, default.paste_ratio),
+            paste_len: ratio(pasted, pastes, default.paste_len),
+            newline_ratio: ratio(newlines, typed, default.newline_ratio),
+            target_len: trace.end_content.chars().count(),
+        }
+    }
+
+    /// Generates a trace starting from an empty document. The same seed
+    /// always generates the same trace.
+    pub fn generate(&self, seed: u64) -> Recorder {
+        let mut rng = Rng(seed);
+        let mut recorder = Recorder::new("");
+        let mut len = 0;
+        let mut cursor = 0;
+        let max_patches = self.target_len.saturating_mul(MAX_PATCHES_PER_CHAR);
+
+        while len < self.target_len && recorder.len() < max_patches {
+            if !rng.chance(self.locality) {
+                cursor = rng.below(len + 1);
+            }
+
+            let burst = rng.geometric(self.burst_len);
+
+            if len > 0 && rng.chance(self.delete_ratio) {
+                for _ in 0..burst.min(cursor) {
+                    recorder.edit(&Edit::remove(CharOffset(cursor - 1)..CharOffset(cursor)));
+                    recorder.commit();
+                    cursor -= 1;
+                    len -= 1;
+                }
+            } else {
+                for _ in 0..burst {
+                    let text_len = match rng.chance(self.paste_ratio) {
+                        true => rng.geometric(self.paste_len),
+                        false => 1,
+                    };
+                    let text = rng.text(text_len, self.newline_ratio);
+
+                    recorder.edit(&Edit::insert(CharOffset(cursor), &text));
+                    recorder.commit();
+                    cursor += text_len;
+                    len += text_len;
+                }
+            }
+        }
+
+        recorder
+    }
+}
+
+/// A splitmix64 generator, so that traces only depend on their seed and not
+/// on the version of an external crate.
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a float uniformly distributed in `[0, 1)`.
+    #[inline]
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    #[inline]
+    pub(crate) fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    /// Returns an integer uniformly distributed in `[0, n)`.
+    #[inline]
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns a geometrically distributed integer of at least 1 with the
+    /// given mean.
+    #[inline]
+    fn geometric(&mut self, mean: f64) -> usize {
+        if mean <= 1.0 {
+            return 1;
+        }
+        let p = 1.0 / mean;
+        1 + ((1.0 - self.next_f64()).ln() / (1.0 - p).ln()) as usize
+    }
+
+    /// Returns `len` chars of lowercase words separated by spaces and line
+    /// breaks.
+    fn text(&mut self, len: usize, newline_ratio: f64) -> String {
+        (0..len)
+            .map(|_| {
+                if self.chance(newline_ratio) {
+                    '\n'
+                } else if self.chance(1.0 / 6.0) {
+                    ' '
+                } else {
+                    (b'a' + self.below(26) as u8) as char
+                }
+            })
+            .collect()
+    }
+}
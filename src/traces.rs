@@ -0,0 +1,163 @@
+//! Discovery and selection of the editing traces replayed by the benchmarks.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crdt_testdata::{load_testing_data, TestData};
+
+/// The environment variable setting the directory traces are looked up in.
+pub const DIR_VAR: &str = "ROPE_BENCHES_TRACES_DIR";
+
+/// The environment variable listing the names of the traces to replay,
+/// separated by commas. Every trace in the directory is replayed if unset.
+pub const INCLUDE_VAR: &str = "ROPE_BENCHES_TRACES";
+
+/// The environment variable listing the names of the traces not to replay,
+/// separated by commas.
+pub const EXCLUDE_VAR: &str = "ROPE_BENCHES_TRACES_EXCLUDE";
+
+pub const DEFAULT_DIR: &str = "./traces";
+
+/// The extension of trace files, which isn't part of their name.
+pub const EXTENSION: &str = ".json.gz";
+
+/// A trace file found in the traces directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceFile {
+    /// The name of the file without its extension.
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl TraceFile {
+    #[inline]
+    pub fn load(&self) -> TestData {
+        load_testing_data(&self.path.to_string_lossy())
+    }
+}
+
+#[derive(Debug)]
+pub enum TraceError {
+    /// The traces directory couldn't be read.
+    Dir { dir: PathBuf, err: io::Error },
+
+    /// Some of the traces that were asked for don't exist.
+    Missing {
+        dir: PathBuf,
+        missing: Vec<String>,
+        available: Vec<String>,
+    },
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dir { dir, err } => {
+                write!(f, "couldn't read the traces in {}: {err}", dir.display())
+            }
+            Self::Missing {
+                dir,
+                missing,
+                available,
+            } => {
+                write!(
+                    f,
+                    "no trace named {} in {}, available traces are: {}",
+                    missing.join(", "),
+                    dir.display(),
+                    available.join(", "),
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+/// Returns every trace in `dir`, sorted by name.
+pub fn discover(dir: &Path) -> Result<Vec<TraceFile>, TraceError> {
+    let entries = std::fs::read_dir(dir).map_err(|err| TraceError::Dir {
+        dir: dir.to_owned(),
+        err,
+    })?;
+
+    let mut traces = Vec::new();
+
+    for entry in entries {
+        let path = entry
+            .map_err(|err| TraceError::Dir {
+                dir: dir.to_owned(),
+                err,
+            })?
+            .path();
+
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(EXTENSION))
+            .map(String::from);
+
+        if let Some(name) = name {
+            traces.push(TraceFile { name, path });
+        }
+    }
+
+    traces.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(traces)
+}
+
+/// Keeps the traces named in `include`, or all of them if it's empty, minus
+/// the ones named in `exclude`.
+pub fn select(
+    dir: &Path,
+    traces: Vec<TraceFile>,
+    include: &[&str],
+    exclude: &[&str],
+) -> Result<Vec<TraceFile>, TraceError> {
+    let missing = include
+        .iter()
+        .chain(exclude)
+        .filter(|name| !traces.iter().any(|trace| trace.name == **name))
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+
+    if !missing.is_empty() {
+        return Err(TraceError::Missing {
+            dir: dir.to_owned(),
+            missing,
+            available: traces.into_iter().map(|trace| trace.name).collect(),
+        });
+    }
+
+    Ok(traces
+        .into_iter()
+        .filter(|trace| include.is_empty() || include.contains(&trace.name.as_str()))
+        .filter(|trace| !exclude.contains(&trace.name.as_str()))
+        .collect())
+}
+
+/// Returns the traces directory set by the [`DIR_VAR`] environment variable,
+/// or the default one.
+pub fn dir_from_env() -> PathBuf {
+    std::env::var_os(DIR_VAR).map_or_else(|| PathBuf::from(DEFAULT_DIR), PathBuf::from)
+}
+
+/// Returns the traces selected by the [`DIR_VAR`], [`INCLUDE_VAR`] and
+/// [`EXCLUDE_VAR`] environment variables.
+pub fn from_env() -> Result<Vec<TraceFile>, TraceError> {
+    let dir = dir_from_env();
+    let include = std::env::var(INCLUDE_VAR).unwrap_or_default();
+    let exclude = std::env::var(EXCLUDE_VAR).unwrap_or_default();
+
+    let traces = discover(&dir)?;
+    select(&dir, traces, &split_names(&include), &split_names(&exclude))
+}
+
+#[inline]
+fn split_names(list: &str) -> Vec<&str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect()
+}
@@ -0,0 +1,124 @@
+//! Variants of traces in non-Latin scripts, to exercise the conversions
+//! between chars, bytes and UTF-16 code units the ropes do.
+
+use std::fmt;
+
+use crdt_testdata::{TestData, TestPatch, TestTxn};
+
+/// The environment variable listing the scripts every trace is also replayed
+/// in, separated by commas: `cjk`, `emoji`, `combining` or `mixed`.
+pub const ENV_VAR: &str = "ROPE_BENCHES_UNICODE";
+
+/// The script characters are remapped to. Every character is remapped to a
+/// single one, so the positions of the patches in chars don't change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    /// CJK ideographs, 3 bytes and 1 UTF-16 code unit each.
+    Cjk,
+
+    /// Emoji, 4 bytes and 2 UTF-16 code units each.
+    Emoji,
+
+    /// Latin text where half of the letters are combining marks, 2 bytes
+    /// and 1 UTF-16 code unit each.
+    Combining,
+
+    /// A mix of ASCII and of the other scripts.
+    Mixed,
+}
+
+impl Script {
+    /// Returns the character `ch` is remapped to. Whitespace is kept, so that
+    /// the documents keep their lines.
+    #[inline]
+    pub fn remap(&self, ch: char) -> char {
+        if ch.is_whitespace() {
+            return ch;
+        }
+
+        let code = ch as u32;
+
+        let remapped = match self {
+            Self::Cjk => 0x4E00 + code % 0x5200,
+            Self::Emoji => 0x1F300 + code % 0x300,
+            Self::Combining if ch.is_alphabetic() && code % 2 == 1 => 0x300 + code % 0x70,
+            Self::Combining => code,
+            Self::Mixed => match code % 4 {
+                0 => code,
+                1 => return Self::Cjk.remap(ch),
+                2 => return Self::Emoji.remap(ch),
+                _ => 0x300 + code % 0x70,
+            },
+        };
+
+        char::from_u32(remapped).unwrap_or(ch)
+    }
+
+    #[inline]
+    pub fn remap_str(&self, s: &str) -> String {
+        s.chars().map(|ch| self.remap(ch)).collect()
+    }
+
+    /// Returns the trace with every character of its contents and of the
+    /// text it inserts remapped to the script.
+    pub fn apply(&self, trace: &TestData) -> TestData {
+        let mut remapped = trace.clone();
+        remapped.start_content = self.remap_str(&trace.start_content);
+        remapped.end_content = self.remap_str(&trace.end_content);
+        remapped.txns = trace
+            .txns
+            .iter()
+            .map(|txn| TestTxn {
+                patches: txn
+                    .patches
+                    .iter()
+                    .map(|TestPatch(pos, del, ins)| TestPatch(*pos, *del, self.remap_str(ins)))
+                    .collect(),
+            })
+            .collect();
+        remapped
+    }
+}
+
+impl std::str::FromStr for Script {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cjk" => Ok(Self::Cjk),
+            "emoji" => Ok(Self::Emoji),
+            "combining" => Ok(Self::Combining),
+            "mixed" => Ok(Self::Mixed),
+            _ => Err(format!("unknown script {s:?}")),
+        }
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Cjk => "cjk",
+            Self::Emoji => "emoji",
+            Self::Combining => "combining",
+            Self::Mixed => "mixed",
+        })
+    }
+}
+
+/// Returns the scripts listed in [`ENV_VAR`].
+pub fn from_env() -> Vec<Script> {
+    let Ok(scripts) = std::env::var(ENV_VAR) else {
+        return Vec::new();
+    };
+
+    scripts
+        .split(',')
+        .map(str::trim)
+        .filter(|script| !script.is_empty())
+        .map(|script| {
+            script
+                .parse()
+                .unwrap_or_else(|err| panic!("invalid {ENV_VAR}: {err}"))
+        })
+        .collect()
+}
@@ -0,0 +1,156 @@
+//! Editing ropes with offsets in UTF-16 code units, like language servers and
+//! JavaScript front ends address documents.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::metric::{Metric, Utf16Offset};
+use crate::rope::{Operation, Rope};
+
+/// The environment variable which, when set, also replays the traces on
+/// every [`Utf16Rope`] with offsets in UTF-16 code units.
+pub const ENV_VAR: &str = "ROPE_BENCHES_UTF16";
+
+#[inline]
+pub fn enabled() -> bool {
+    std::env::var_os(ENV_VAR).is_some()
+}
+
+/// A rope which can convert UTF-16 code unit offsets into its own offsets.
+pub trait Utf16Rope: Rope {
+    /// Converts an offset in UTF-16 code units into an offset in
+    /// [`Rope::Offset`].
+    fn offset_of_utf16(&self, utf16_offset: usize) -> usize;
+
+    /// Returns the length of the rope in UTF-16 code units.
+    fn utf16_len(&self) -> usize;
+}
+
+/// A rope edited with offsets in UTF-16 code units, which it has to convert
+/// before every edit.
+pub struct Utf16<R>(R);
+
+impl<R> Utf16<R> {
+    #[inline]
+    pub fn new(rope: R) -> Self {
+        Self(rope)
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &R {
+        &self.0
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.0
+    }
+}
+
+impl<R: Utf16Rope> Rope for Utf16<R> {
+    const NAME: &'static str = "Utf16";
+
+    type Offset = Utf16Offset;
+
+    const UNSUPPORTED: &'static [Operation] = R::UNSUPPORTED;
+
+    #[inline]
+    fn name() -> Cow<'static, str> {
+        Cow::Owned(format!("Utf16<{}>", R::name()))
+    }
+
+    #[inline]
+    fn from_str(s: &str) -> Self {
+        Self(R::from_str(s))
+    }
+
+    #[inline]
+    fn insert(&mut self, at: usize, s: &str) {
+        let at = self.0.offset_of_utf16(at);
+        self.0.insert(at, s);
+    }
+
+    #[inline]
+    fn remove(&mut self, range: Range<usize>) {
+        let range = self.0.offset_of_utf16(range.start)..self.0.offset_of_utf16(range.end);
+        self.0.remove(range);
+    }
+
+    #[inline]
+    fn replace(&mut self, range: Range<usize>, s: &str) {
+        let range = self.0.offset_of_utf16(range.start)..self.0.offset_of_utf16(range.end);
+        self.0.replace(range, s);
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.0.utf16_len()
+    }
+
+    #[inline]
+    fn contents(&self) -> String {
+        self.0.contents()
+    }
+}
+
+/// `String` has no index, so it scans from the start of the text like an
+/// editor without any UTF-16 support would.
+impl Utf16Rope for String {
+    #[inline]
+    fn offset_of_utf16(&self, utf16_offset: usize) -> usize {
+        Utf16Offset(utf16_offset).to_byte_in(self)
+    }
+
+    #[inline]
+    fn utf16_len(&self) -> usize {
+        Utf16Offset::measure(self).get()
+    }
+}
+
+impl Utf16Rope for crop::Rope {
+    #[inline]
+    fn offset_of_utf16(&self, utf16_offset: usize) -> usize {
+        self.byte_of_utf16_code_unit(utf16_offset)
+    }
+
+    #[inline]
+    fn utf16_len(&self) -> usize {
+        self.utf16_len()
+    }
+}
+
+impl Utf16Rope for jumprope::JumpRope {
+    #[inline]
+    fn offset_of_utf16(&self, utf16_offset: usize) -> usize {
+        self.wchars_to_chars(utf16_offset)
+    }
+
+    #[inline]
+    fn utf16_len(&self) -> usize {
+        self.len_wchars()
+    }
+}
+
+impl Utf16Rope for ropey::Rope {
+    #[inline]
+    fn offset_of_utf16(&self, utf16_offset: usize) -> usize {
+        self.utf16_cu_to_char(utf16_offset)
+    }
+
+    #[inline]
+    fn utf16_len(&self) -> usize {
+        self.len_utf16_cu()
+    }
+}
+
+impl Utf16Rope for xi_rope::Rope {
+    #[inline]
+    fn offset_of_utf16(&self, utf16_offset: usize) -> usize {
+        self.count_base_units::<xi_rope::rope::Utf16CodeUnitsMetric>(utf16_offset)
+    }
+
+    #[inline]
+    fn utf16_len(&self) -> usize {
+        self.measure::<xi_rope::rope::Utf16CodeUnitsMetric>()
+    }
+}
@@ -0,0 +1,197 @@
+//! Checks that replaying a trace on a rope produces the same text as
+//! replaying it on a reference rope.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use crdt_testdata::{TestData, TestPatch, TestTxn};
+
+use crate::rope::Rope;
+
+/// The environment variable setting every how many transactions the contents
+/// of the ropes are compared to the reference while verifying them. Only the
+/// final contents are compared if unset.
+pub const ENV_VAR: &str = "ROPE_BENCHES_CHECKPOINTS";
+
+/// The length in bytes of the documents above which traces aren't verified,
+/// since comparing the contents of a rope to the reference needs a copy of
+/// them. The benchmarks still check that the ropes end with the right length.
+pub const MAX_LEN: usize = 256 << 20;
+
+/// Returns the checkpoint interval set by [`ENV_VAR`].
+pub fn checkpoint_interval() -> Option<usize> {
+    let interval = std::env::var(ENV_VAR).ok()?;
+
+    match interval.parse() {
+        Ok(0) | Err(_) => panic!("{ENV_VAR} should be a positive integer, got {interval:?}"),
+        Ok(interval) => Some(interval),
+    }
+}
+
+/// The point where a rope stopped having the same contents as the reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The index of the first transaction after which the contents differ.
+    pub txn: usize,
+
+    /// The byte offset of the first difference.
+    pub offset: usize,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "contents differ from the reference after transaction {} at byte {}",
+            self.txn, self.offset
+        )
+    }
+}
+
+impl std::error::Error for Divergence {}
+
+/// A trace replayed on a [`ropey::Rope`], with the hash of its contents at
+/// every checkpoint.
+pub struct Reference {
+    /// The trace in chars.
+    trace: Rc<TestData>,
+    interval: Option<usize>,
+    hashes: Vec<u64>,
+}
+
+impl Reference {
+    /// Replays `trace`, which has to be in chars, hashing the contents every
+    /// `interval` transactions.
+    pub fn new(trace: Rc<TestData>, interval: Option<usize>) -> Self {
+        let mut hashes = Vec::new();
+
+        if let Some(interval) = interval.filter(|_| is_verified(&trace)) {
+            let mut text = ropey::Rope::from_str(&trace.start_content);
+            for (idx, txn) in trace.txns.iter().enumerate() {
+                apply(&mut text, txn);
+                if (idx + 1) % interval == 0 {
+                    hashes.push(hash(&text.to_string()));
+                }
+            }
+        }
+
+        Self {
+            trace,
+            interval,
+            hashes,
+        }
+    }
+
+    /// Returns whether [`verify`](Self::verify) checks anything, which it
+    /// doesn't for traces with documents larger than [`MAX_LEN`].
+    #[inline]
+    pub fn is_verified(&self) -> bool {
+        is_verified(&self.trace)
+    }
+
+    /// Replays `trace`, which has to be in the offsets of `R`, checking its
+    /// contents at every checkpoint and at the end. Transactions are applied
+    /// with [`Rope::apply_transaction`] if `batched` is set, and patch by patch
+    /// otherwise.
+    pub fn verify<R: Rope>(&self, trace: &TestData, batched: bool) -> Result<(), Divergence> {
+        if !self.is_verified() {
+            return Ok(());
+        }
+
+        let mut rope = R::from_str(&trace.start_content);
+        let mut verified = 0;
+
+        for (idx, txn) in trace.txns.iter().enumerate() {
+            match batched {
+                true => rope.apply_transaction(&txn.patches),
+                false => apply(&mut rope, txn),
+            }
+
+            let Some(interval) = self.interval else {
+                continue;
+            };
+
+            if (idx + 1) % interval == 0 {
+                if hash(&rope.contents()) != self.hashes[(idx + 1) / interval - 1] {
+                    return Err(self.find_divergence::<R>(trace, verified, batched));
+                }
+                verified = idx + 1;
+            }
+        }
+
+        if rope.contents() != self.trace.end_content {
+            return Err(self.find_divergence::<R>(trace, verified, batched));
+        }
+
+        Ok(())
+    }
+
+    /// Replays `trace` on both `R` and the reference, comparing their contents
+    /// after every transaction past the first `verified` ones.
+    fn find_divergence<R: Rope>(
+        &self,
+        trace: &TestData,
+        verified: usize,
+        batched: bool,
+    ) -> Divergence {
+        let mut rope = R::from_str(&trace.start_content);
+        let mut text = ropey::Rope::from_str(&self.trace.start_content);
+
+        for (idx, (txn, reference_txn)) in trace.txns.iter().zip(&self.trace.txns).enumerate() {
+            match batched {
+                true => rope.apply_transaction(&txn.patches),
+                false => apply(&mut rope, txn),
+            }
+            apply(&mut text, reference_txn);
+
+            if idx < verified {
+                continue;
+            }
+
+            let contents = rope.contents();
+            if text != contents.as_str() {
+                return Divergence {
+                    txn: idx,
+                    offset: first_difference(&contents, &text.to_string()),
+                };
+            }
+        }
+
+        // Every transaction matched the reference, so the trace has none or
+        // the reference itself doesn't end with the trace's end contents.
+        let contents = rope.contents();
+        Divergence {
+            txn: trace.txns.len().saturating_sub(1),
+            offset: first_difference(&contents, &self.trace.end_content),
+        }
+    }
+}
+
+#[inline]
+fn apply<R: Rope>(rope: &mut R, txn: &TestTxn) {
+    for TestPatch(pos, del, ins) in &txn.patches {
+        rope.replace(*pos..*pos + del, ins);
+    }
+}
+
+#[inline]
+fn is_verified(trace: &TestData) -> bool {
+    trace.start_content.len().max(trace.end_content.len()) <= MAX_LEN
+}
+
+#[inline]
+fn hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[inline]
+fn first_difference(a: &str, b: &str) -> usize {
+    a.bytes()
+        .zip(b.bytes())
+        .position(|(a, b)| a != b)
+        .unwrap_or(a.len().min(b.len()))
+}
@@ -0,0 +1,118 @@
+//! Windows of traces, to time a phase of a trace instead of all of it.
+
+use std::fmt;
+use std::ops::Range;
+
+use crdt_testdata::{TestData, TestPatch, TestTxn};
+
+/// The environment variable listing the windows of transactions replayed
+/// instead of the whole traces, separated by commas. Windows are written
+/// like Rust ranges: `K..M`, `K..` or `..M`. Unlike for whole traces,
+/// creating the rope from the document a window starts at isn't timed.
+pub const ENV_VAR: &str = "ROPE_BENCHES_WINDOW";
+
+/// A range of the transactions of a trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    pub start: usize,
+
+    /// The end of the window, or `None` if it goes to the end of the trace.
+    pub end: Option<usize>,
+}
+
+impl Window {
+    /// Returns the transactions of the window which are in a trace with
+    /// `len` transactions.
+    #[inline]
+    pub fn range(&self, len: usize) -> Range<usize> {
+        let end = self.end.map_or(len, |end| end.min(len));
+        self.start.min(end)..end
+    }
+
+    /// Returns the trace replaying the transactions of the window from the
+    /// document as it was before the first of them, or `None` if the trace
+    /// has no transactions in the window.
+    pub fn apply(&self, trace: &TestData) -> Option<TestData> {
+        let range = self.range(trace.txns.len());
+
+        if range.is_empty() {
+            return None;
+        }
+
+        let mut text = ropey::Rope::from_str(&trace.start_content);
+
+        replay(&mut text, &trace.txns[..range.start]);
+        let start_content = text.to_string();
+
+        replay(&mut text, &trace.txns[range.clone()]);
+        let end_content = text.to_string();
+
+        let mut windowed = trace.clone();
+        windowed.start_content = start_content;
+        windowed.end_content = end_content;
+        windowed.txns = trace.txns[range].to_vec();
+        Some(windowed)
+    }
+}
+
+impl std::str::FromStr for Window {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid window {s:?}");
+
+        let (start, end) = s.split_once("..").ok_or_else(invalid)?;
+
+        let start = match start.trim() {
+            "" => 0,
+            start => start.parse().map_err(|_| invalid())?,
+        };
+
+        let end = match end.trim() {
+            "" => None,
+            end => Some(end.parse().map_err(|_| invalid())?),
+        };
+
+        match end {
+            Some(end) if end <= start => Err(format!("empty window {s:?}")),
+            _ => Ok(Self { start, end }),
+        }
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "{}..{end}", self.start),
+            None => write!(f, "{}..", self.start),
+        }
+    }
+}
+
+#[inline]
+fn replay(text: &mut ropey::Rope, txns: &[TestTxn]) {
+    for txn in txns {
+        for TestPatch(pos, del, ins) in &txn.patches {
+            text.remove(*pos..pos + del);
+            text.insert(*pos, ins);
+        }
+    }
+}
+
+/// Returns the windows listed in [`ENV_VAR`].
+pub fn from_env() -> Vec<Window> {
+    let Ok(windows) = std::env::var(ENV_VAR) else {
+        return Vec::new();
+    };
+
+    windows
+        .split(',')
+        .map(str::trim)
+        .filter(|window| !window.is_empty())
+        .map(|window| {
+            window
+                .parse()
+                .unwrap_or_else(|err| panic!("invalid {ENV_VAR}: {err}"))
+        })
+        .collect()
+}